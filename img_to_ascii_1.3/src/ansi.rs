@@ -0,0 +1,203 @@
+// ansi art files: the text with SGR escape sequences for the colors, so the art keeps its colors when it's cat-ed later.
+// they can also be written in code page 437 with a SAUCE record at the end, the way ansi art editors and viewers expect.
+use crate::{palette, Canvas, ColorDepth, Encoder, RenderOptions, Rgb};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the characters of code page 437 from 0x80 to 0xff, the first half is ascii
+pub(crate) const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', //
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', //
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', //
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐', //
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧', //
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀', //
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', //
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{a0}',
+];
+
+// the byte of a character in code page 437. characters it doesn't have, like braille, turn into '?'
+pub fn to_cp437(c: char) -> u8 {
+    if c.is_ascii() {
+        return c as u8;
+    }
+    CP437_HIGH.iter().position(|&high| high == c).map_or(b'?', |i| 0x80 + i as u8)
+}
+
+// the SGR parameters that set a color, `base` is 30 for the text and 40 for the background
+fn color_code(rgb: Rgb, depth: ColorDepth, base: u8) -> String {
+    match depth {
+        ColorDepth::Ansi16 => {
+            let n = palette::nearest_ansi16(rgb);
+            // the bright colors are 90-97 and 100-107
+            if n < 8 {
+                (base + n).to_string()
+            } else {
+                (base + 60 + n - 8).to_string()
+            }
+        }
+        ColorDepth::Ansi256 => format!("{};5;{}", base + 8, palette::nearest_ansi256(rgb)),
+        _ => format!("{};2;{};{};{}", base + 8, rgb.0, rgb.1, rgb.2),
+    }
+}
+
+// the metadata record ansi art tools read from the end of the file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sauce {
+    pub title: String,
+    pub author: String,
+    pub group: String,
+}
+
+impl Sauce {
+    // the 128 bytes of the record for art of this size, `file_size` is how long the file is without the record
+    pub fn record(&self, file_size: usize, width: usize, height: usize) -> Vec<u8> {
+        // text fields are padded with spaces, the font name with zeroes
+        let field = |text: &str, len: usize, pad: u8| {
+            let mut bytes: Vec<u8> = text.chars().map(to_cp437).take(len).collect();
+            bytes.resize(len, pad);
+            bytes
+        };
+        let mut record = Vec::with_capacity(128);
+        record.extend(b"SAUCE00");
+        record.extend(field(&self.title, 35, b' '));
+        record.extend(field(&self.author, 20, b' '));
+        record.extend(field(&self.group, 20, b' '));
+        record.extend(today().as_bytes());
+        record.extend((file_size as u32).to_le_bytes());
+        // data type 1 is character, file type 1 is ansi
+        record.extend([1, 1]);
+        record.extend((width as u16).to_le_bytes());
+        record.extend((height as u16).to_le_bytes());
+        record.extend([0; 4]);
+        // no comments, no flags
+        record.extend([0, 0]);
+        record.extend(field("IBM VGA", 22, 0));
+        record
+    }
+}
+
+// the date as CCYYMMDD
+fn today() -> String {
+    let days = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() / 86400) as i64;
+    // days since 1970-01-01 to a date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}{:02}{:02}", year, month, day)
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnsiEncoder {
+    // write code page 437 instead of utf-8, with dos line endings
+    pub cp437: bool,
+    pub sauce: Option<Sauce>,
+}
+
+impl AnsiEncoder {
+    // the art with escape sequences, before it's turned into bytes
+    pub fn text(&self, canvas: &Canvas, depth: ColorDepth) -> String {
+        let newline = if self.cp437 { "\r\n" } else { "\n" };
+        let mut text = String::new();
+        for row in &canvas.rows {
+            let mut line = String::new();
+            let mut current = (None, None);
+            for cell in row {
+                let colors = if depth.is_colored() { (cell.fg, cell.bg) } else { (None, None) };
+                if colors != current {
+                    // start from the default colors, so a cell without a background doesn't keep the last one
+                    let mut codes = vec![String::from("0")];
+                    codes.extend(colors.0.map(|fg| color_code(fg, depth, 30)));
+                    codes.extend(colors.1.map(|bg| color_code(bg, depth, 40)));
+                    line += &format!("\x1b[{}m", codes.join(";"));
+                    current = colors;
+                }
+                line += &cell.glyph;
+            }
+            if current == (None, None) {
+                line.truncate(line.trim_end().len());
+            } else {
+                // reset at the end of every line so the colors don't spill into whatever comes after
+                line += "\x1b[0m";
+            }
+            text += &line;
+            text += newline;
+        }
+        text
+    }
+}
+
+impl Encoder for AnsiEncoder {
+    fn name(&self) -> &str {
+        "ansi"
+    }
+
+    fn extension(&self) -> &str {
+        "ans"
+    }
+
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn media_type(&self) -> &str {
+        if self.cp437 {
+            "text/plain; charset=ibm437"
+        } else {
+            "text/plain; charset=utf-8"
+        }
+    }
+
+    fn encode(&self, canvas: &Canvas, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        let text = self.text(canvas, options.color);
+        let mut bytes = if self.cp437 { text.chars().map(to_cp437).collect() } else { text.into_bytes() };
+        if let Some(sauce) = &self.sauce {
+            let record = sauce.record(bytes.len(), canvas.width(), canvas.height());
+            // the record comes after an end of file character, so `type` on dos stops before it
+            bytes.push(0x1a);
+            bytes.extend(record);
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::Cell;
+
+    #[test]
+    fn sauce_record_layout() {
+        let sauce = Sauce { title: String::from("Café"), author: String::from("me"), group: String::new() };
+        let record = sauce.record(1000, 80, 25);
+        assert_eq!(record.len(), 128);
+        assert_eq!(&record[0..7], b"SAUCE00");
+        assert_eq!(&record[7..12], b"Caf\x82 ");
+        assert!(record[12..42].iter().all(|&b| b == b' '));
+        assert_eq!(&record[42..44], b"me");
+        assert!(record[62..82].iter().all(|&b| b == b' '));
+        assert!(record[82..90].iter().all(u8::is_ascii_digit));
+        assert_eq!(&record[90..94], &1000u32.to_le_bytes());
+        assert_eq!(&record[94..96], &[1, 1]);
+        assert_eq!(&record[96..100], &[80, 0, 25, 0]);
+        assert_eq!(&record[100..106], &[0; 6]);
+        assert_eq!(&record[106..113], b"IBM VGA");
+        assert!(record[113..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn sauce_comes_after_an_end_of_file_character() {
+        let mut canvas = Canvas::new();
+        canvas.push_row(vec![Cell::plain("█"), Cell::plain("░")]);
+        let encoder = AnsiEncoder { cp437: true, sauce: Some(Sauce::default()) };
+        let bytes = encoder.encode(&canvas, &RenderOptions::default()).unwrap();
+        assert_eq!(&bytes[..5], b"\xdb\xb0\r\n\x1a");
+        assert_eq!(bytes.len(), 5 + 128);
+        assert_eq!(&bytes[5 + 90..5 + 94], &4u32.to_le_bytes());
+    }
+}
@@ -0,0 +1,212 @@
+// the art as a grid of cells, in between turning an image into characters and writing them out.
+// every mode draws onto a Canvas and every output format is made out of one, so any mode works with any format.
+use colored::{ColoredString, Colorize};
+use unicode_width::UnicodeWidthChar;
+
+pub type Rgb = (u8, u8, u8);
+
+// one character of the art. a block or ascii cell can be a few characters wide, e.g. "██"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: String,
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+}
+
+impl Cell {
+    // a cell without colors
+    pub fn plain(glyph: impl Into<String>) -> Cell {
+        Cell { glyph: glyph.into(), fg: None, bg: None }
+    }
+
+    pub fn colored(glyph: impl Into<String>, fg: Rgb, bg: Option<Rgb>) -> Cell {
+        Cell { glyph: glyph.into(), fg: Some(fg), bg }
+    }
+
+    // the cell as a string the terminal shows in color
+    pub fn to_colored(&self) -> ColoredString {
+        let mut cell = self.glyph.normal();
+        if let Some((r, g, b)) = self.fg {
+            cell = cell.truecolor(r, g, b);
+        }
+        if let Some((r, g, b)) = self.bg {
+            cell = cell.on_truecolor(r, g, b);
+        }
+        cell
+    }
+}
+
+// how many columns a character takes up in a terminal or editor with a monospace font, from the unicode east asian
+// width data: 0 for combining marks, control characters and other characters that go on top of the one before,
+// 2 for wide east asian characters and emoji, 1 for everything else. block elements and braille are 1,
+// even though some cjk fonts draw blocks wider
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+// cells next to each other with the same colors. blank cells don't show their text color,
+// so they join the run they're in as long as the background matches
+pub fn runs(row: &[Cell]) -> Vec<&[Cell]> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for (i, cell) in row.iter().enumerate().skip(1) {
+        let first = &row[start];
+        if cell.bg != first.bg || (cell.fg != first.fg && !cell.glyph.trim().is_empty()) {
+            runs.push(&row[start..i]);
+            start = i;
+        }
+    }
+    if start < row.len() {
+        runs.push(&row[start..]);
+    }
+    runs
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Canvas {
+    // one row per line of text
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Canvas {
+    pub fn new() -> Canvas {
+        Canvas::default()
+    }
+
+    pub fn push_row(&mut self, row: Vec<Cell>) {
+        self.rows.push(row);
+    }
+
+    // how many lines of text the art is
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    // how many characters wide the widest line is, spaces included
+    pub fn width(&self) -> usize {
+        let row_width = |row: &Vec<Cell>| row.iter().map(|cell| cell.glyph.chars().count()).sum();
+        self.rows.iter().map(row_width).max().unwrap_or(0)
+    }
+
+    // whether any cell has a color
+    pub fn is_colored(&self) -> bool {
+        self.rows.iter().flatten().any(|cell| cell.fg.is_some() || cell.bg.is_some())
+    }
+
+    // the plain text, without the spaces at the end of the lines
+    pub fn to_text(&self) -> String {
+        let mut final_ascii = String::new();
+        for row in &self.rows {
+            let asciiline: String = row.iter().map(|cell| cell.glyph.as_str()).collect();
+            final_ascii += &format!("{}{}", asciiline.trim_end(), "\n");
+        }
+        final_ascii
+    }
+
+    // make sure every character is exactly one column wide, otherwise the columns can't line up
+    pub fn check_widths(&self) -> Result<(), String> {
+        for (y, row) in self.rows.iter().enumerate() {
+            if let Some(c) = row.iter().flat_map(|cell| cell.glyph.chars()).find(|&c| char_width(c) != 1) {
+                return Err(format!(
+                    "'{}' (U+{:04X}) on line {} is {} columns wide, so the columns wouldn't line up. use characters that are 1 column wide",
+                    c.escape_debug(),
+                    c as u32,
+                    y + 1,
+                    char_width(c)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // the plain text with every line filled up with `blank` to the same width, so nothing moves
+    // no matter how the lines end or where the text is pasted
+    pub fn to_padded_text(&self, blank: char) -> Result<String, String> {
+        self.check_widths()?;
+        let width = self.width();
+        let mut text = String::new();
+        for row in &self.rows {
+            let line: String = row.iter().map(|cell| cell.glyph.as_str()).collect();
+            let padding = width - line.chars().count();
+            text += &line;
+            text.extend(std::iter::repeat_n(blank, padding));
+            text += "\n";
+        }
+        Ok(text)
+    }
+
+    // a standalone html page, see html.rs
+    pub fn to_html(&self) -> String {
+        crate::html::encode(self)
+    }
+
+    // the rows as strings the terminal shows in color
+    pub fn to_colored(&self) -> Vec<Vec<ColoredString>> {
+        self.rows.iter().map(|row| row.iter().map(Cell::to_colored).collect()).collect()
+    }
+
+    // exactly what print writes into a terminal that shows colors, escape sequences and all
+    pub fn to_term(&self) -> String {
+        if !self.is_colored() {
+            return self.to_text();
+        }
+        let mut term = String::new();
+        for row in &self.rows {
+            for cell in row {
+                // the same sequences as the colored crate: the background first, then the text color
+                let mut codes = Vec::new();
+                codes.extend(cell.bg.map(|(r, g, b)| format!("48;2;{};{};{}", r, g, b)));
+                codes.extend(cell.fg.map(|(r, g, b)| format!("38;2;{};{};{}", r, g, b)));
+                if codes.is_empty() {
+                    term += &cell.glyph;
+                } else {
+                    term += &format!("\x1b[{}m{}\x1b[0m", codes.join(";"), cell.glyph);
+                }
+            }
+            term += "\n";
+        }
+        term
+    }
+
+    // print into the terminal, in color if the canvas has any
+    pub fn print(&self) {
+        if !self.is_colored() {
+            print!("{}", self.to_text());
+            return;
+        }
+        for row in &self.rows {
+            for cell in row {
+                print!("{}", cell.to_colored())
+            }
+            println!()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_widths() {
+        for c in ['a', '#', '█', '▀', '░', '⣿', '⡀', 'é'] {
+            assert_eq!(char_width(c), 1, "{}", c);
+        }
+        for c in ['漢', 'ア', '한', '😀', '🎨'] {
+            assert_eq!(char_width(c), 2, "{}", c);
+        }
+        for c in ['\u{301}', '\u{200b}', '\x1b', '\0'] {
+            assert_eq!(char_width(c), 0, "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn check_widths_rejects_wide_characters() {
+        let mut canvas = Canvas::new();
+        canvas.push_row(vec![Cell::plain("█"), Cell::plain("⣿")]);
+        assert!(canvas.check_widths().is_ok());
+        canvas.push_row(vec![Cell::plain("😀"), Cell::plain("a")]);
+        let error = canvas.check_widths().unwrap_err();
+        assert!(error.contains("U+1F600") && error.contains("line 2"), "{}", error);
+    }
+}
@@ -0,0 +1,76 @@
+// asciicast v2 recordings of playing an animation in the terminal, for asciinema-player and `asciinema play`.
+// the file is a json header line and then one line per frame: [seconds since the start, "o", what was printed].
+use crate::Frame;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// a json string, with quotes
+pub fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            '\n' => json += "\\n",
+            '\r' => json += "\\r",
+            '\t' => json += "\\t",
+            // the escape character of the colors ends up as \u001b
+            c if c.is_control() => json += &format!("\\u{:04x}", c as u32),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+// the recording of the frames being printed one after another, like `play` does.
+// frames without their own delay are up for `msdelay` milliseconds
+pub fn recording(frames: &[Frame], msdelay: u64, title: &str) -> String {
+    let width = frames.iter().map(|frame| frame.canvas.width()).max().unwrap_or(0);
+    // one more line for the cursor after the last row, otherwise the top row scrolls away
+    let height = frames.iter().map(|frame| frame.canvas.height()).max().unwrap_or(0) + 1;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let mut cast = format!(
+        "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}, \"title\": {}, \"env\": {{\"TERM\": \"xterm-256color\"}}}}\n",
+        width,
+        height,
+        timestamp,
+        json_string(title)
+    );
+    let mut time = 0;
+    for frame in frames {
+        // the terminal turns every \n into \r\n, and that's what gets recorded
+        let output = frame.canvas.to_term().replace('\n', "\r\n");
+        cast += &format!("[{}, \"o\", {}]\n", seconds(time), json_string(&output));
+        time += frame.delay.unwrap_or(msdelay);
+    }
+    // an empty event at the end, so the last frame stays up for its delay too
+    cast += &format!("[{}, \"o\", \"\"]\n", seconds(time));
+    cast
+}
+
+fn seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Canvas, Cell};
+
+    #[test]
+    fn events_follow_the_delays() {
+        let frame = |glyph: &str, delay| {
+            let mut canvas = Canvas::new();
+            canvas.push_row(vec![Cell::plain(glyph), Cell::plain(glyph)]);
+            Frame { canvas, delay }
+        };
+        let cast = recording(&[frame("a", None), frame("b", Some(1500))], 200, "cat \"1\"");
+        let lines: Vec<&str> = cast.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 2, \"height\": 2, "));
+        assert!(lines[0].contains("\"title\": \"cat \\\"1\\\"\""));
+        assert!(lines[1].starts_with("[0.000, \"o\", ") && lines[1].contains("aa\\r\\n"));
+        assert!(lines[2].starts_with("[0.200, \"o\", ") && lines[2].contains("bb\\r\\n"));
+        assert_eq!(lines[3], "[1.700, \"o\", \"\"]");
+    }
+}
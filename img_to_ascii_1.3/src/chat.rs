@@ -0,0 +1,198 @@
+// formats for pasting art into chats and forums: irc with mirc color codes, bbcode with [color] tags
+// and markdown code blocks. irc and forums don't trim lines for us, so the spaces at the end of lines are left out.
+use crate::{canvas::runs, palette, Canvas, ColorDepth, Encoder, RenderOptions, Rgb};
+
+// starts a color, followed by the text color and optionally a comma and the background
+const IRC_COLOR: char = '\x03';
+// turns every color and style off
+const IRC_RESET: char = '\x0f';
+
+// irc messages with mirc colors, one message per line. --color-depth 16 sticks to the 16 colors every client has,
+// anything more uses all 99 of them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IrcEncoder;
+
+impl IrcEncoder {
+    pub fn text(&self, canvas: &Canvas, depth: ColorDepth) -> String {
+        let extended = depth != ColorDepth::Ansi16;
+        let mut text = String::new();
+        for row in &canvas.rows {
+            let mut line = String::new();
+            // the colors that are on at the end of the line so far
+            let mut current: (Option<u8>, Option<u8>) = (None, None);
+            for run in runs(row) {
+                let glyphs: String = run.iter().map(|cell| cell.glyph.as_str()).collect();
+                let blank = glyphs.trim().is_empty();
+                let color = |rgb: Option<Rgb>| rgb.filter(|_| depth.is_colored()).map(|rgb| palette::nearest_mirc(rgb, extended));
+                // blank cells don't show their text color, so they keep whatever is on
+                let fg = if blank { current.0 } else { color(run[0].fg) };
+                let colors = (fg, color(run[0].bg));
+                if colors != current {
+                    // there's no way to turn just the background off
+                    if (current.0.is_some() && colors.0.is_none()) || (current.1.is_some() && colors.1.is_none()) {
+                        line.push(IRC_RESET);
+                    }
+                    match colors {
+                        // always two digits, so a digit in the art isn't read as part of the color
+                        (Some(fg), Some(bg)) => line += &format!("{}{:02},{:02}", IRC_COLOR, fg, bg),
+                        // a background needs a text color in front of it
+                        (None, Some(bg)) => line += &format!("{}{:02},{:02}", IRC_COLOR, bg, bg),
+                        (Some(fg), None) => line += &format!("{}{:02}", IRC_COLOR, fg),
+                        (None, None) => {}
+                    }
+                    current = colors;
+                }
+                line += &glyphs;
+            }
+            if current.1.is_none() {
+                line.truncate(line.trim_end().len());
+            }
+            text += &line;
+            text += "\n";
+        }
+        text
+    }
+}
+
+impl Encoder for IrcEncoder {
+    fn name(&self) -> &str {
+        "irc"
+    }
+
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn encode(&self, canvas: &Canvas, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        Ok(self.text(canvas, options.color).into_bytes())
+    }
+}
+
+// forum posts with [color=#rrggbb] around every run of the same text color, in a monospace [font].
+// bbcode has no backgrounds, so colored half blocks only keep their top half
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BbcodeEncoder;
+
+impl BbcodeEncoder {
+    pub fn text(&self, canvas: &Canvas) -> String {
+        let mut text = String::from("[font=monospace]\n");
+        for row in &canvas.rows {
+            let mut line = String::new();
+            let mut current = None;
+            // text after the last [/color], which can be trimmed
+            let mut tail = 0;
+            for run in runs(row) {
+                let glyphs: String = run.iter().map(|cell| cell.glyph.as_str()).collect();
+                // blank runs stay inside the color they're in, so neighbouring runs share one tag
+                if !glyphs.trim().is_empty() && run[0].fg != current {
+                    if current.is_some() {
+                        line += "[/color]";
+                        tail = line.len();
+                    }
+                    if let Some((r, g, b)) = run[0].fg {
+                        line += &format!("[color=#{:02x}{:02x}{:02x}]", r, g, b);
+                    }
+                    current = run[0].fg;
+                }
+                line += &glyphs;
+            }
+            if current.is_some() {
+                // spaces at the end go before the closing tag, where they can be trimmed
+                let colored = line[tail..].trim_end().len();
+                line.truncate(tail + colored);
+                line += "[/color]";
+            } else {
+                line.truncate(line.trim_end().len());
+            }
+            text += &line;
+            text += "\n";
+        }
+        text += "[/font]\n";
+        text
+    }
+}
+
+impl Encoder for BbcodeEncoder {
+    fn name(&self) -> &str {
+        "bbcode"
+    }
+
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn encode(&self, canvas: &Canvas, _options: &RenderOptions) -> Result<Vec<u8>, String> {
+        Ok(self.text(canvas).into_bytes())
+    }
+}
+
+// the plain art in a fenced code block, for markdown chats like discord, github or matrix
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarkdownEncoder {
+    // cut lines off after this many characters, chats wrap long lines and that breaks the art
+    pub max_width: Option<usize>,
+}
+
+impl MarkdownEncoder {
+    // with `pad`, every line is filled up to the same width with `blank`
+    pub fn text(&self, canvas: &Canvas, pad: Option<char>) -> Result<String, String> {
+        let text = match pad {
+            Some(blank) => canvas.to_padded_text(blank)?,
+            None => canvas.to_text(),
+        };
+        let mut art = String::new();
+        for line in text.lines() {
+            let line: String = line.chars().take(self.max_width.unwrap_or(usize::MAX)).collect();
+            art += if pad.is_some() { &line } else { line.trim_end() };
+            art += "\n";
+        }
+        // the fence has to be longer than any row of backticks in the art, e.g. with a custom ramp
+        let mut fence = String::from("```");
+        while art.contains(&fence) {
+            fence.push('`');
+        }
+        Ok(format!("{}text\n{}{}\n", fence, art, fence))
+    }
+}
+
+impl Encoder for MarkdownEncoder {
+    fn name(&self) -> &str {
+        "markdown"
+    }
+
+    fn extension(&self) -> &str {
+        "md"
+    }
+
+    fn supports_color(&self) -> bool {
+        false
+    }
+
+    fn media_type(&self) -> &str {
+        "text/markdown; charset=utf-8"
+    }
+
+    fn encode(&self, canvas: &Canvas, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        let pad = options.pad.then(|| options.blank());
+        Ok(self.text(canvas, pad)?.into_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cell;
+
+    #[test]
+    fn irc_colors_are_two_digits_and_reset_before_plain_cells() {
+        let mut canvas = Canvas::new();
+        canvas.push_row(vec![
+            Cell::colored("1", (255, 255, 255), Some((0, 0, 0))),
+            Cell::colored("2", (255, 255, 255), Some((0, 0, 0))),
+            Cell::plain("3"),
+            Cell::plain(" "),
+        ]);
+        assert_eq!(IrcEncoder.text(&canvas, ColorDepth::Ansi16), "\x0300,0112\x0f3\n");
+        assert_eq!(IrcEncoder.text(&canvas, ColorDepth::Mono), "123\n");
+    }
+}
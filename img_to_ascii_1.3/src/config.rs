@@ -0,0 +1,156 @@
+// settings from toml config files, so the same long sets of flags don't have to be typed every time.
+//
+// the files are read in this order, later files win:
+//     $XDG_CONFIG_HOME/img_to_ascii/config.toml (~/.config/img_to_ascii/config.toml without XDG_CONFIG_HOME)
+//     ./img_to_ascii.toml
+// top-level keys are the defaults, and [preset.<name>] tables are picked with --preset and win over the defaults.
+// keys are the long names of the command line options:
+//     mode = "braille"
+//     whitespace = true
+//
+//     [preset.slack]
+//     mode = "block"
+//     color = true
+use std::{collections::BTreeMap, env, fs, path::Path, path::PathBuf, str::FromStr};
+pub use toml::Value;
+
+pub type Settings = BTreeMap<String, Value>;
+
+#[derive(Default, Debug, Clone)]
+pub struct Config {
+    pub defaults: Settings,
+    pub presets: BTreeMap<String, Settings>,
+    // the files the settings came from
+    pub sources: Vec<PathBuf>,
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let table = match text.parse::<Value>().map_err(|e| e.to_string())? {
+            Value::Table(table) => table,
+            _ => return Err(String::from("the config has to be a table")),
+        };
+        let mut config = Config::default();
+        for (key, value) in table {
+            if key != "preset" {
+                config.defaults.insert(normalize(&key), value);
+                continue;
+            }
+            let presets = match value {
+                Value::Table(presets) => presets,
+                _ => return Err(String::from("preset has to be a table of [preset.<name>] tables")),
+            };
+            for (name, preset) in presets {
+                match preset {
+                    Value::Table(preset) => {
+                        let preset = preset.into_iter().map(|(key, value)| (normalize(&key), value)).collect();
+                        config.presets.insert(name, preset);
+                    }
+                    _ => return Err(format!("preset.{} has to be a table", name)),
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl Config {
+    // the config files that are looked for, in the order they're read
+    pub fn paths() -> Vec<PathBuf> {
+        let mut paths = Vec::new();
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+        if let Some(config_home) = config_home {
+            paths.push(config_home.join("img_to_ascii").join("config.toml"));
+        }
+        paths.push(PathBuf::from("img_to_ascii.toml"));
+        paths
+    }
+
+    // read every config file that exists
+    pub fn load() -> Result<Config, String> {
+        let mut config = Config::default();
+        for path in Config::paths() {
+            if path.is_file() {
+                config.load_file(&path)?;
+            }
+        }
+        Ok(config)
+    }
+
+    // read a config file on top of the current settings
+    pub fn load_file(&mut self, path: &Path) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let other: Config = text.parse().map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        self.defaults.extend(other.defaults);
+        for (name, preset) in other.presets {
+            self.presets.entry(name).or_default().extend(preset);
+        }
+        self.sources.push(path.to_path_buf());
+        Ok(())
+    }
+
+    // the defaults with the preset on top
+    pub fn settings(&self, preset: Option<&str>) -> Result<Settings, String> {
+        let mut settings = self.defaults.clone();
+        if let Some(name) = preset {
+            match self.presets.get(name) {
+                Some(preset) => settings.extend(preset.clone()),
+                None => {
+                    let known: Vec<&str> = self.presets.keys().map(String::as_str).collect();
+                    return Err(if known.is_empty() {
+                        format!("unknown preset '{}', no presets are defined", name)
+                    } else {
+                        format!("unknown preset '{}', expected one of {}", name, known.join(", "))
+                    });
+                }
+            }
+        }
+        Ok(settings)
+    }
+}
+
+// color_depth and color-depth are the same key
+fn normalize(key: &str) -> String {
+    key.replace('_', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+mode = "braille"
+color_depth = "256"
+
+[preset.slack]
+mode = "block"
+color = true
+"#;
+
+    #[test]
+    fn presets_win_over_the_defaults() {
+        let config: Config = CONFIG.parse().unwrap();
+        let defaults = config.settings(None).unwrap();
+        assert_eq!(defaults.get("mode").and_then(Value::as_str), Some("braille"));
+        assert_eq!(defaults.get("color-depth").and_then(Value::as_str), Some("256"));
+        assert!(!defaults.contains_key("color"));
+        let slack = config.settings(Some("slack")).unwrap();
+        assert_eq!(slack.get("mode").and_then(Value::as_str), Some("block"));
+        assert_eq!(slack.get("color").and_then(Value::as_bool), Some(true));
+        assert_eq!(slack.get("color-depth").and_then(Value::as_str), Some("256"));
+        let error = config.settings(Some("discord")).unwrap_err();
+        assert!(error.contains("expected one of slack"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_presets_that_arent_tables() {
+        assert!("preset = 1".parse::<Config>().is_err());
+        assert!("[preset]\nslack = 1".parse::<Config>().is_err());
+        assert!("mode = ".parse::<Config>().is_err());
+    }
+}
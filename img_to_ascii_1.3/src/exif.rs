@@ -0,0 +1,168 @@
+// cameras and phones save photos the way the sensor saw them and write down in the exif data which way
+// the camera was held, so viewers turn them upright. this reads that orientation out of jpeg, png, webp
+// and tiff files, without a whole exif library, and turns the image the same way.
+use image::DynamicImage;
+
+// the tag of the orientation in the first ifd
+const ORIENTATION: u16 = 0x0112;
+
+// the orientation from 1 to 8, None if the file doesn't say
+pub fn orientation(bytes: &[u8]) -> Option<u16> {
+    let tiff = if bytes.starts_with(&[0xff, 0xd8]) {
+        jpeg_exif(bytes)?
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_exif(bytes)?
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        webp_exif(bytes)?
+    } else {
+        bytes
+    };
+    tiff_orientation(tiff).filter(|orientation| (1..=8).contains(orientation))
+}
+
+// the exif data is in an APP1 segment that starts with "Exif\0\0"
+fn jpeg_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    loop {
+        let (marker, length) = (*bytes.get(pos + 1)?, u16::from_be_bytes([*bytes.get(pos + 2)?, *bytes.get(pos + 3)?]) as usize);
+        // the image data starts at SOS, there's no exif after that
+        if bytes[pos] != 0xff || marker == 0xda {
+            return None;
+        }
+        let segment = bytes.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xe1 && segment.starts_with(b"Exif\0\0") {
+            return Some(&segment[6..]);
+        }
+        pos += 2 + length;
+    }
+}
+
+// png has it in an eXIf chunk
+fn png_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut pos = 8;
+    loop {
+        let length = u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?) as usize;
+        let kind = bytes.get(pos + 4..pos + 8)?;
+        if kind == b"IDAT" {
+            return None;
+        }
+        if kind == b"eXIf" {
+            return bytes.get(pos + 8..pos + 8 + length);
+        }
+        // length, type, data and crc
+        pos += 12 + length;
+    }
+}
+
+// webp has it in an EXIF chunk, sometimes with the "Exif\0\0" of jpeg in front
+fn webp_exif(bytes: &[u8]) -> Option<&[u8]> {
+    let mut pos = 12;
+    loop {
+        let length = u32::from_le_bytes(bytes.get(pos + 4..pos + 8)?.try_into().ok()?) as usize;
+        if bytes.get(pos..pos + 4)? == b"EXIF" {
+            let chunk = bytes.get(pos + 8..pos + 8 + length)?;
+            return Some(chunk.strip_prefix(b"Exif\0\0").unwrap_or(chunk));
+        }
+        // chunks are padded to an even length
+        pos += 8 + length + length % 2;
+    }
+}
+
+// exif data is a little tiff file: a byte order, the offset of the first ifd, and in there 12 byte entries
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let little_endian = match tiff.get(0..4)? {
+        b"II*\0" => true,
+        b"MM\0*" => false,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| {
+        let bytes = [*tiff.get(pos)?, *tiff.get(pos + 1)?];
+        Some(if little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    };
+    let u32_at = |pos: usize| {
+        let bytes: [u8; 4] = tiff.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    };
+    let ifd = u32_at(4)? as usize;
+    (0..u16_at(ifd)? as usize)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|&entry| u16_at(entry) == Some(ORIENTATION))
+        .and_then(|entry| u16_at(entry + 8))
+}
+
+// turn the image the way the orientation says, so it's upright
+pub fn apply(img: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        // mirrored along the top left to bottom right diagonal
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        // mirrored along the other diagonal
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+// whether the orientation swaps the width and the height
+pub fn swaps_sides(orientation: u16) -> bool {
+    orientation >= 5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, Rgb, RgbImage};
+
+    // a big endian tiff with just the orientation in its first ifd
+    fn tiff(orientation: u16) -> Vec<u8> {
+        let mut tiff = b"MM\0*\0\0\0\x08\0\x01".to_vec();
+        tiff.extend_from_slice(&ORIENTATION.to_be_bytes());
+        tiff.extend_from_slice(&[0, 3, 0, 0, 0, 1]);
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        tiff
+    }
+
+    #[test]
+    fn reads_orientation_from_jpeg() {
+        let exif = [b"Exif\0\0".as_slice(), &tiff(6)].concat();
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe1];
+        jpeg.extend_from_slice(&(exif.len() as u16 + 2).to_be_bytes());
+        jpeg.extend_from_slice(&exif);
+        jpeg.extend_from_slice(&[0xff, 0xda, 0, 2]);
+        assert_eq!(orientation(&jpeg), Some(6));
+        assert_eq!(orientation(&tiff(9)), None);
+        assert_eq!(orientation(&[0xff, 0xd8, 0xff, 0xda, 0, 2]), None);
+    }
+
+    #[test]
+    fn apply_turns_the_image_upright() {
+        // a 3x2 image with the first two pixels of the top row marked
+        let mut img = RgbImage::new(3, 2);
+        img.put_pixel(0, 0, Rgb([255, 0, 0]));
+        img.put_pixel(1, 0, Rgb([0, 255, 0]));
+        let img = DynamicImage::ImageRgb8(img);
+        let find = |img: &DynamicImage, color: [u8; 3]| img.pixels().find(|(_, _, pixel)| pixel.0[..3] == color).map(|(x, y, _)| (x, y));
+        // where the first and second pixel end up for every orientation
+        let expected = [
+            (1, (0, 0), (1, 0)),
+            (2, (2, 0), (1, 0)),
+            (3, (2, 1), (1, 1)),
+            (4, (0, 1), (1, 1)),
+            (5, (0, 0), (0, 1)),
+            (6, (1, 0), (1, 1)),
+            (7, (1, 2), (1, 1)),
+            (8, (0, 2), (0, 1)),
+        ];
+        for (orientation, first, second) in expected {
+            let upright = apply(img.clone(), orientation);
+            let size = if swaps_sides(orientation) { (2, 3) } else { (3, 2) };
+            assert_eq!(upright.dimensions(), size, "orientation {}", orientation);
+            assert_eq!(find(&upright, [255, 0, 0]), Some(first), "orientation {}", orientation);
+            assert_eq!(find(&upright, [0, 255, 0]), Some(second), "orientation {}", orientation);
+        }
+    }
+}
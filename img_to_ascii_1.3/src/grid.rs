@@ -0,0 +1,298 @@
+// the cells themselves as a file, for tools that want the glyphs and colors instead of scraping text,
+// and for turning the art into other formats later without the image. json is for reading, bin is the same but smaller.
+// both hold a list of frames with their delays, a single image is one frame without a delay.
+use crate::{Canvas, Cell, Encoder, Frame, RenderOptions, Rgb};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+// the start of every bin file, followed by the version
+const MAGIC: &[u8; 4] = b"IAGR";
+const VERSION: u8 = 1;
+// a frame without its own delay
+const NO_DELAY: u32 = u32::MAX;
+
+// what a json or bin file holds
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Grid {
+    // the mode the art was drawn with
+    pub mode: String,
+    pub frames: Vec<Frame>,
+}
+
+// how a grid looks as json. the size is only there for other tools, it comes out of the cells anyway
+#[derive(Serialize, Deserialize)]
+struct JsonGrid {
+    version: u8,
+    mode: String,
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    height: usize,
+    frames: Vec<JsonFrame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonFrame {
+    delay: Option<u64>,
+    rows: Vec<Vec<JsonCell>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonCell {
+    glyph: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fg: Option<Rgb>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bg: Option<Rgb>,
+}
+
+// whether a file is a json or bin grid instead of an image
+pub fn is_grid(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("bin"))
+}
+
+impl Grid {
+    pub fn new(mode: &str, frames: Vec<Frame>) -> Grid {
+        Grid { mode: mode.to_string(), frames }
+    }
+
+    // how many characters wide and how many lines tall the biggest frame is
+    pub fn dimensions(&self) -> (usize, usize) {
+        let width = self.frames.iter().map(|frame| frame.canvas.width()).max().unwrap_or(0);
+        let height = self.frames.iter().map(|frame| frame.canvas.height()).max().unwrap_or(0);
+        (width, height)
+    }
+
+    // {"version":1,"mode":"block","width":2,"height":1,"frames":[{"delay":null,"rows":[
+    // [{"glyph":"█","fg":[255,0,0]},{"glyph":" ","fg":[0,0,0],"bg":[0,0,255]}]
+    // ]}]}
+    // on one line. cells without colors leave out "fg" and "bg"
+    pub fn to_json(&self) -> String {
+        let (width, height) = self.dimensions();
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| JsonFrame {
+                delay: frame.delay,
+                rows: frame
+                    .canvas
+                    .rows
+                    .iter()
+                    .map(|row| row.iter().map(|cell| JsonCell { glyph: cell.glyph.clone(), fg: cell.fg, bg: cell.bg }).collect())
+                    .collect(),
+            })
+            .collect();
+        let grid = JsonGrid { version: VERSION, mode: self.mode.clone(), width, height, frames };
+        // plain structs of strings and numbers always serialize
+        serde_json::to_string(&grid).unwrap() + "\n"
+    }
+
+    // everything is little endian:
+    //     "IAGR", version (u8), mode (u8 length + utf-8), width (u32), height (u32), frame count (u32)
+    //     every frame: delay in ms (u32, 0xffffffff for none), row count (u32)
+    //     every row: cell count (u32)
+    //     every cell: flags (u8, 1 = has fg, 2 = has bg), glyph (u8 length + utf-8), fg (3 bytes), bg (3 bytes)
+    pub fn to_bin(&self) -> Vec<u8> {
+        let (width, height) = self.dimensions();
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        push_str(&mut bytes, &self.mode);
+        for n in [width, height, self.frames.len()] {
+            bytes.extend((n as u32).to_le_bytes());
+        }
+        for frame in &self.frames {
+            let delay = frame.delay.map_or(NO_DELAY, |delay| delay.min(NO_DELAY as u64 - 1) as u32);
+            bytes.extend(delay.to_le_bytes());
+            bytes.extend((frame.canvas.rows.len() as u32).to_le_bytes());
+            for row in &frame.canvas.rows {
+                bytes.extend((row.len() as u32).to_le_bytes());
+                for cell in row {
+                    bytes.push(cell.fg.is_some() as u8 | (cell.bg.is_some() as u8) << 1);
+                    push_str(&mut bytes, &cell.glyph);
+                    for (r, g, b) in cell.fg.into_iter().chain(cell.bg) {
+                        bytes.extend([r, g, b]);
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    // read a json or bin file back in
+    pub fn decode(bytes: &[u8]) -> Result<Grid, String> {
+        if bytes.starts_with(MAGIC) {
+            return Grid::from_bin(bytes);
+        }
+        let grid: JsonGrid = serde_json::from_slice(bytes).map_err(|e| format!("invalid cell grid: {}", e))?;
+        Grid::from_json(grid)
+    }
+
+    pub fn load(path: &str) -> Result<Grid, String> {
+        let bytes = fs::read(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+        Grid::decode(&bytes).map_err(|e| format!("can't read {}: {}", path, e))
+    }
+
+    fn from_json(grid: JsonGrid) -> Result<Grid, String> {
+        if grid.version != VERSION {
+            return Err(String::from("invalid cell grid: unknown version"));
+        }
+        let frames = grid
+            .frames
+            .into_iter()
+            .map(|frame| {
+                let mut canvas = Canvas::new();
+                for row in frame.rows {
+                    canvas.push_row(row.into_iter().map(|cell| Cell { glyph: cell.glyph, fg: cell.fg, bg: cell.bg }).collect());
+                }
+                Frame { canvas, delay: frame.delay }
+            })
+            .collect();
+        Ok(Grid::new(&grid.mode, frames))
+    }
+
+    fn from_bin(bytes: &[u8]) -> Result<Grid, String> {
+        let mut reader = Reader { bytes, pos: MAGIC.len() };
+        if reader.u8()? != VERSION {
+            return Err(String::from("invalid cell grid: unknown version"));
+        }
+        let mode = reader.str()?;
+        // the size is only there for other tools, it comes out of the cells anyway
+        let (_width, _height, frame_count) = (reader.u32()?, reader.u32()?, reader.u32()?);
+        let mut frames = Vec::new();
+        for _ in 0..frame_count {
+            let delay = match reader.u32()? {
+                NO_DELAY => None,
+                delay => Some(delay as u64),
+            };
+            let mut canvas = Canvas::new();
+            for _ in 0..reader.u32()? {
+                let mut cells = Vec::new();
+                for _ in 0..reader.u32()? {
+                    let flags = reader.u8()?;
+                    let glyph = reader.str()?;
+                    let fg = if flags & 1 != 0 { Some(reader.rgb()?) } else { None };
+                    let bg = if flags & 2 != 0 { Some(reader.rgb()?) } else { None };
+                    cells.push(Cell { glyph, fg, bg });
+                }
+                canvas.push_row(cells);
+            }
+            frames.push(Frame { canvas, delay });
+        }
+        Ok(Grid::new(&mode, frames))
+    }
+}
+
+// a string with its length in front, glyphs and mode names are never longer than 255 bytes
+fn push_str(bytes: &mut Vec<u8>, text: &str) {
+    let mut end = text.len().min(255);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    bytes.push(end as u8);
+    bytes.extend(&text.as_bytes()[..end]);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let taken = self.bytes.get(self.pos..self.pos + len).ok_or_else(|| String::from("invalid cell grid: the file is cut off"))?;
+        self.pos += len;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn rgb(&mut self) -> Result<Rgb, String> {
+        let bytes = self.take(3)?;
+        Ok((bytes[0], bytes[1], bytes[2]))
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let len = self.u8()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| String::from("invalid cell grid: text that isn't utf-8"))
+    }
+}
+
+// the json and bin formats. on their own they write one frame, `animate` writes whole animations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridEncoder {
+    pub binary: bool,
+}
+
+impl Encoder for GridEncoder {
+    fn name(&self) -> &str {
+        if self.binary {
+            "bin"
+        } else {
+            "json"
+        }
+    }
+
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn media_type(&self) -> &str {
+        if self.binary {
+            "application/octet-stream"
+        } else {
+            "application/json"
+        }
+    }
+
+    fn encode(&self, canvas: &Canvas, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        let grid = Grid::new(options.renderer_name(), vec![Frame { canvas: canvas.clone(), delay: None }]);
+        Ok(if self.binary { grid.to_bin() } else { grid.to_json().into_bytes() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid {
+        let mut first = Canvas::new();
+        first.push_row(vec![Cell::colored("█", (255, 0, 0), None), Cell::colored("\"", (0, 0, 0), Some((0, 0, 255)))]);
+        first.push_row(vec![Cell::plain("⣿"), Cell::plain("\x1b")]);
+        let mut second = Canvas::new();
+        second.push_row(vec![Cell::plain("ab")]);
+        Grid::new("braille", vec![Frame { canvas: first, delay: Some(40) }, Frame { canvas: second, delay: None }])
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let grid = grid();
+        assert_eq!(Grid::decode(grid.to_json().as_bytes()), Ok(grid));
+    }
+
+    #[test]
+    fn bin_round_trip() {
+        let grid = grid();
+        let bytes = grid.to_bin();
+        assert!(bytes.starts_with(b"IAGR\x01"));
+        assert_eq!(Grid::decode(&bytes), Ok(grid));
+    }
+
+    #[test]
+    fn rejects_broken_grids() {
+        let bytes = grid().to_bin();
+        assert!(Grid::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Grid::decode(b"{\"version\": 2, \"mode\": \"block\", \"frames\": []}").is_err());
+        assert!(Grid::decode(b"\xff\xfe").is_err());
+        assert!(Grid::decode(b"{\"version\": 1, \"mode\": \"block\", \"frames\": [{\"rows\": [[{\"glyph\": \"a\", \"fg\": [1, 2]}]]}]}").is_err());
+        // nesting this deep is turned away instead of overflowing the stack
+        assert!(Grid::decode("[".repeat(100_000).as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// html pages out of canvases. cells next to each other with the same colors share one <span>,
+// and colors that are used more than once get a css class instead of being written out every time.
+use crate::{canvas::runs, html_escape, Canvas, Frame, Rgb};
+use std::collections::HashMap;
+
+// how the art is laid out on the page. line-height 1 keeps the rows of blocks touching
+pub const STYLE: &str = "body { margin: 0; background: #fff; color: #000; }
+pre { margin: 0; font-family: \"DejaVu Sans Mono\", Menlo, Consolas, monospace; font-size: 10px; line-height: 1; }
+";
+
+// a color as css #rrggbb
+pub(crate) fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// the css classes of the colors, "f<n>" for text colors and "b<n>" for backgrounds
+#[derive(Default, Debug, Clone)]
+pub struct StyleSheet {
+    fg: HashMap<Rgb, usize>,
+    bg: HashMap<Rgb, usize>,
+}
+
+impl StyleSheet {
+    // classes for every color that's used by more than one run of cells, the most used colors get the shortest names
+    pub fn new<'a>(canvases: impl IntoIterator<Item = &'a Canvas>) -> StyleSheet {
+        let (mut fg, mut bg) = (HashMap::new(), HashMap::new());
+        for canvas in canvases {
+            for row in &canvas.rows {
+                for run in runs(row) {
+                    if let Some(color) = run[0].fg {
+                        *fg.entry(color).or_insert(0) += 1;
+                    }
+                    if let Some(color) = run[0].bg {
+                        *bg.entry(color).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        StyleSheet { fg: classes(fg), bg: classes(bg) }
+    }
+
+    // the css rules for the classes
+    pub fn css(&self) -> String {
+        let mut rules: Vec<(usize, String)> = Vec::new();
+        rules.extend(self.fg.iter().map(|(&color, &n)| (2 * n, format!(".f{} {{ color: {} }}\n", n, hex(color)))));
+        rules.extend(self.bg.iter().map(|(&color, &n)| (2 * n + 1, format!(".b{} {{ background: {} }}\n", n, hex(color)))));
+        rules.sort();
+        rules.into_iter().map(|(_, rule)| rule).collect()
+    }
+
+    // the canvas as the inside of a <pre>, one line per row
+    pub fn markup(&self, canvas: &Canvas) -> String {
+        let mut markup = String::new();
+        for row in &canvas.rows {
+            let mut line = String::new();
+            // plain cells at the end of the line are trimmed like in txt files
+            let mut plain_tail = 0;
+            for run in runs(row) {
+                let text: String = run.iter().map(|cell| html_escape(&cell.glyph)).collect();
+                let (fg, bg) = (run[0].fg, run[0].bg);
+                if fg.is_none() && bg.is_none() {
+                    plain_tail = line.len();
+                    line += &text;
+                    continue;
+                }
+                let (mut class, mut style) = (Vec::new(), Vec::new());
+                match fg.map(|color| (color, self.fg.get(&color))) {
+                    Some((_, Some(n))) => class.push(format!("f{}", n)),
+                    Some((color, None)) => style.push(format!("color:{}", hex(color))),
+                    None => {}
+                }
+                match bg.map(|color| (color, self.bg.get(&color))) {
+                    Some((_, Some(n))) => class.push(format!("b{}", n)),
+                    Some((color, None)) => style.push(format!("background:{}", hex(color))),
+                    None => {}
+                }
+                line += "<span";
+                if !class.is_empty() {
+                    line += &format!(" class=\"{}\"", class.join(" "));
+                }
+                if !style.is_empty() {
+                    line += &format!(" style=\"{}\"", style.join(";"));
+                }
+                line += &format!(">{}</span>", text);
+                plain_tail = line.len();
+            }
+            let trimmed = line[plain_tail..].trim_end().len();
+            line.truncate(plain_tail + trimmed);
+            markup += &line;
+            markup += "\n";
+        }
+        markup
+    }
+}
+
+// number the colors that are used more than once, from the most used one
+fn classes(counts: HashMap<Rgb, usize>) -> HashMap<Rgb, usize> {
+    let mut repeated: Vec<(Rgb, usize)> = counts.into_iter().filter(|&(_, count)| count > 1).collect();
+    repeated.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    repeated.into_iter().enumerate().map(|(n, (color, _))| (color, n)).collect()
+}
+
+// a complete html5 page
+pub fn document(title: &str, css: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        html_escape(title),
+        STYLE,
+        css,
+        body
+    )
+}
+
+// a page with the canvas on it
+pub fn encode(canvas: &Canvas) -> String {
+    let styles = StyleSheet::new([canvas]);
+    document("ascii art", &styles.css(), &format!("<pre>\n{}</pre>\n", styles.markup(canvas)))
+}
+
+// the controls of the player, and how they look
+const PLAYER_STYLE: &str = "#controls { padding: 4px; font-family: sans-serif; font-size: 14px; }
+#controls input[type=range] { width: 300px; vertical-align: middle; }
+";
+
+const PLAYER_CONTROLS: &str = "<div id=\"controls\">
+<button id=\"play\">Pause</button>
+<input id=\"slider\" type=\"range\" min=\"0\" value=\"0\">
+<span id=\"counter\"></span>
+<label>Speed <select id=\"speed\">
+<option value=\"0.25\">0.25x</option>
+<option value=\"0.5\">0.5x</option>
+<option value=\"1\" selected>1x</option>
+<option value=\"2\">2x</option>
+<option value=\"4\">4x</option>
+</select></label>
+<label><input id=\"loop\" type=\"checkbox\" checked> Loop</label>
+</div>
+";
+
+// shows one frame at a time, each for its data-delay divided by the speed
+const PLAYER_SCRIPT: &str = "<script>
+(function () {
+  var frames = document.querySelectorAll('#frames pre');
+  var play = document.getElementById('play');
+  var slider = document.getElementById('slider');
+  var counter = document.getElementById('counter');
+  var speed = document.getElementById('speed');
+  var loop = document.getElementById('loop');
+  var current = 0, playing = false, timer = null;
+  slider.max = frames.length - 1;
+  function show(i) {
+    frames[current].hidden = true;
+    current = i;
+    frames[current].hidden = false;
+    slider.value = i;
+    counter.textContent = (i + 1) + '/' + frames.length;
+  }
+  function schedule() {
+    clearTimeout(timer);
+    if (playing) {
+      timer = setTimeout(step, frames[current].dataset.delay / speed.value);
+    }
+  }
+  function step() {
+    if (current + 1 < frames.length) {
+      show(current + 1);
+    } else if (loop.checked) {
+      show(0);
+    } else {
+      setPlaying(false);
+      return;
+    }
+    schedule();
+  }
+  function setPlaying(on) {
+    playing = on;
+    play.textContent = on ? 'Pause' : 'Play';
+    schedule();
+  }
+  play.onclick = function () {
+    // play from the start again after stopping at the end
+    if (!playing && current + 1 == frames.length && !loop.checked) {
+      show(0);
+    }
+    setPlaying(!playing);
+  };
+  slider.oninput = function () {
+    show(+slider.value);
+    schedule();
+  };
+  speed.onchange = schedule;
+  show(0);
+  setPlaying(frames.length > 1);
+})();
+</script>
+";
+
+// a page that plays the frames, with play/pause, a frame slider, the speed and looping.
+// frames without their own delay are up for `msdelay` milliseconds
+pub fn player(frames: &[Frame], msdelay: u64) -> String {
+    // every frame shares the same classes
+    let styles = StyleSheet::new(frames.iter().map(|frame| &frame.canvas));
+    let mut body = String::from("<div id=\"frames\">\n");
+    for (i, frame) in frames.iter().enumerate() {
+        let hidden = if i == 0 { "" } else { " hidden" };
+        let delay = frame.delay.unwrap_or(msdelay);
+        body += &format!("<pre data-delay=\"{}\"{}>\n{}</pre>\n", delay, hidden, styles.markup(&frame.canvas));
+    }
+    body += "</div>\n";
+    body += PLAYER_CONTROLS;
+    body += PLAYER_SCRIPT;
+    document("ascii animation", &(PLAYER_STYLE.to_string() + &styles.css()), &body)
+}
@@ -1,12 +1,110 @@
 extern crate image;
 use colored::*;
-use image::GenericImageView;
-use std::{borrow::Cow, fs, fs::File, io, io::Write, str, thread, time::{Duration, Instant}};
+use image::{
+    codecs::gif::{GifDecoder, Repeat},
+    AnimationDecoder, DynamicImage, GenericImageView, ImageDecoder,
+};
+use raster::RasterEncoder;
+use std::{borrow::Cow, cmp::Ordering, fmt, fs, fs::File, io, io::{Read, Write}, path::{Path, PathBuf}, str, str::FromStr, sync::{atomic::{AtomicBool, Ordering as AtomicOrdering}, mpsc, Arc}, thread, time::{Duration, Instant}};
+
+// progress messages like "Processing ..." go to stderr so stdout only has the art in it,
+// and can be turned off completely with set_quiet
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, AtomicOrdering::Relaxed)
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(AtomicOrdering::Relaxed)
+}
+
+// eprintln!, unless quiet mode is on
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::is_quiet() {
+            eprintln!($($arg)*)
+        }
+    };
+}
+
+pub mod ansi;
+pub mod canvas;
+pub mod chat;
+pub mod cast;
+pub mod config;
+pub mod exif;
+pub mod grid;
+pub mod html;
+pub mod options;
+pub mod palette;
+pub mod raster;
+pub mod registry;
+pub mod serve;
+pub mod svg;
+pub mod watch;
+
+pub use canvas::{Canvas, Cell, Rgb};
+pub use options::{ColorDepth, Crop, Length, Mode, OutputFormat, RenderOptions, Size};
+pub use registry::{Encoder, Registry, Renderer};
+
 // ===================================================== helper functions =====================================================
 
+// load an image from a file, or from stdin if the name is "-".
+// the format of stdin is guessed from the first few bytes.
+pub fn load_image(filename: &str) -> image::ImageResult<DynamicImage> {
+    if filename == "-" {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        decode_image(&bytes, None)
+    } else {
+        open_upright(filename)
+    }
+}
+
+// decode an image, turned upright if its exif data says the camera was held sideways.
+// the format is guessed from the first few bytes, or comes from the extension of `path` for formats that can't be guessed
+pub fn decode_image(bytes: &[u8], path: Option<&str>) -> image::ImageResult<DynamicImage> {
+    let mut reader = image::io::Reader::new(io::Cursor::new(bytes));
+    if let Some(format) = path.and_then(|path| image::ImageFormat::from_path(path).ok()) {
+        reader.set_format(format);
+    }
+    let img = reader.with_guessed_format()?.decode()?;
+    Ok(match exif::orientation(bytes) {
+        Some(orientation) => exif::apply(img, orientation),
+        None => img,
+    })
+}
+
+// image::open, with the image turned upright like decode_image
+pub fn open_upright(path: &str) -> image::ImageResult<DynamicImage> {
+    decode_image(&fs::read(path)?, Some(path))
+}
+
+// image::image_dimensions, with the width and height swapped for images that are turned upright
+pub fn upright_dimensions(path: &str) -> image::ImageResult<(u32, u32)> {
+    let (width, height) = image::image_dimensions(path)?;
+    // the exif data is at the start of the file
+    let mut start = Vec::new();
+    File::open(path)?.take(256 * 1024).read_to_end(&mut start)?;
+    match exif::orientation(&start) {
+        Some(orientation) if exif::swaps_sides(orientation) => Ok((height, width)),
+        _ => Ok((width, height)),
+    }
+}
+
+// load an image and say so, panics with `context` if it can't be loaded
+pub fn open_image(filename: &str, context: &str) -> DynamicImage {
+    let img = load_image(filename).unwrap_or_else(|e| panic!("{} failed: {}", context, e));
+    let (width, height) = img.dimensions();
+    status!("Processing {}: {}x{}", filename, width, height);
+    img
+}
+
 // choose the right character for the pixel's intensity using the NTSC formula
 pub fn get_ascii_char(r: u8, g: u8, b: u8, swap: bool) -> String {
-    let mut characters = vec!["█", "▓", "▒", "░", " "];
+    let mut characters = ["█", "▓", "▒", "░", " "];
     if swap {
         characters.reverse();
     }
@@ -25,52 +123,26 @@ pub fn get_ascii_char(r: u8, g: u8, b: u8, swap: bool) -> String {
 
 // create a string consisting of spaces and █ characters.
 pub fn img_to_asciistring(filename: &str, compress: u32, charamount: usize, swap: bool) -> String {
-    let mut final_ascii = String::new();
-    // load image
-    let img = image::open(filename).expect("img_to_asciistring failed!");
-    let (width, height) = img.dimensions();
-    println!("Processing {}: {}x{}", filename, width, height);
-    for y in 0..height {
-        // only iterate through every n line to reduce the size
-        if y % compress == 0 {
-            let mut asciiline = String::new();
-            for x in 0..width {
-                // only iterate through every n column to reduce the size
-                if x % compress == 0 {
-                    // get the pixel at the (x, y) position and get an ascii character from its rgb value
-                    let pixel: image::Rgba<u8> = img.get_pixel(x, y);
-                    asciiline += &get_ascii_char(pixel.0[0], pixel.0[1], pixel.0[2], swap)
-                        .repeat(charamount);
-                }
-            }
-            final_ascii += &format!("{}{}", asciiline.trim_end(), "\n");
-        }
-    }
-    final_ascii
+    let img = open_image(filename, "img_to_asciistring");
+    image_to_asciistring(&img, compress, charamount, swap)
+}
+
+// same as img_to_asciistring, but for an image that is already loaded
+pub fn image_to_asciistring(img: &DynamicImage, compress: u32, charamount: usize, swap: bool) -> String {
+    let options = RenderOptions::new(Mode::Block).compress(compress).charamount(charamount).invert(swap);
+    image_to_canvas(img, &options).to_text()
 }
 
 // separate function as the output has to be in vector form instead
 pub fn img_to_cblock(filename: &str, compress: u32) -> Vec<Vec<ColoredString>> {
-    let mut final_vec = Vec::new();
-    // load image
-    let img = image::open(filename).expect("img_to_cblock failed!");
-    let (width, height) = img.dimensions();
-    println!("Processing {}: {}x{}", filename, width, height);
-    for y in 0..height {
-        if y % compress == 0 {
-            let mut asciiline = Vec::new();
-            for x in 0..width {
-                if x % compress == 0 {
-                    // get the pixel at the (x, y) position and get an ascii character from its rgb value
-                    let pixel: image::Rgba<u8> = img.get_pixel(x, y);
-                    let colored_string = "███".truecolor(pixel.0[0], pixel.0[1], pixel.0[2]);
-                    asciiline.push(colored_string)
-                }
-            }
-            final_vec.push(asciiline);
-        }
-    }
-    final_vec
+    let img = open_image(filename, "img_to_cblock");
+    image_to_cblock(&img, compress)
+}
+
+// same as img_to_cblock, but for an image that is already loaded
+pub fn image_to_cblock(img: &DynamicImage, compress: u32) -> Vec<Vec<ColoredString>> {
+    let options = RenderOptions::new(Mode::Block).compress(compress).charamount(3).color(ColorDepth::TrueColor);
+    image_to_canvas(img, &options).to_colored()
 }
 
 // turn binary into braille character
@@ -92,266 +164,1155 @@ pub fn bin_to_braille(bin: &str, whitespace: bool) -> char {
 
 // create a string consisting of braille characters. uses whitespaces if specified so.
 pub fn img_to_braillestring(filename: &str, compress: u32, whitespace: bool, swap: bool) -> String {
-    // set the mapping of the braille dots
-    let braillemap = [0, 2, 4, 1, 3, 5, 6, 7];
-    let mut final_ascii: String = String::new();
-    // load the image
-    let img = image::open(filename).expect("img_to_braillestring failed!");
+    let img = open_image(filename, "img_to_braillestring");
+    image_to_braillestring(&img, compress, whitespace, swap)
+}
+
+// same as img_to_braillestring, but for an image that is already loaded
+pub fn image_to_braillestring(img: &DynamicImage, compress: u32, whitespace: bool, swap: bool) -> String {
+    let options = RenderOptions::new(Mode::Braille).compress(compress).whitespace(whitespace).invert(swap);
+    image_to_canvas(img, &options).to_text()
+}
+
+// the characters for the ascii mode, from the darkest pixel to the brightest
+const ASCII_RAMP: [&str; 10] = ["@", "%", "#", "*", "+", "=", "-", ":", ".", " "];
+// the characters for the block mode, from the darkest pixel to the brightest
+const BLOCK_RAMP: [&str; 5] = ["█", "▓", "▒", "░", " "];
+
+// NTSC intensity formula, max intensity is 255
+pub fn intensity(pixel: image::Rgba<u8>) -> f32 {
+    pixel.0[0] as f32 * 0.299 + pixel.0[1] as f32 * 0.587 + pixel.0[2] as f32 * 0.114
+}
+
+// which step of an evenly split ramp an intensity falls on
+fn ramp_index(intensity: f32, steps: usize) -> usize {
+    ((intensity / 256. * steps as f32) as usize).min(steps - 1)
+}
+
+// the same split as get_ascii_char, so the block mode looks the way it always has
+fn block_index(intensity: f32) -> usize {
+    let divisor = 255. / (BLOCK_RAMP.len() + 1) as f32;
+    (((intensity / divisor).floor() - 1.) as usize).min(BLOCK_RAMP.len() - 1)
+}
+
+// a cell with a foreground and optionally a background color, reduced to the color depth
+fn paint(glyph: String, fg: image::Rgba<u8>, bg: Option<image::Rgba<u8>>, depth: ColorDepth) -> Cell {
+    let rgb = |pixel: image::Rgba<u8>| palette::quantize((pixel.0[0], pixel.0[1], pixel.0[2]), depth);
+    Cell::colored(glyph, rgb(fg), bg.map(rgb))
+}
+
+// the average color of some pixels
+fn average_color(pixels: &[image::Rgba<u8>]) -> image::Rgba<u8> {
+    let mut sum = [0u32; 3];
+    for pixel in pixels {
+        for (total, channel) in sum.iter_mut().zip(pixel.0) {
+            *total += channel as u32;
+        }
+    }
+    let count = pixels.len().max(1) as u32;
+    image::Rgba([(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, 255])
+}
+
+// the image turned by --rotate and then mirrored by the flip options. photos are already upright by their exif
+// orientation when they're loaded, so this is only what was asked for on top of that
+pub fn transform_image<'a>(img: &'a DynamicImage, options: &RenderOptions) -> Cow<'a, DynamicImage> {
+    let mut img = Cow::Borrowed(img);
+    img = match options.rotate {
+        90 => Cow::Owned(img.rotate90()),
+        180 => Cow::Owned(img.rotate180()),
+        270 => Cow::Owned(img.rotate270()),
+        _ => img,
+    };
+    if options.flip_horizontal {
+        img = Cow::Owned(img.fliph());
+    }
+    if options.flip_vertical {
+        img = Cow::Owned(img.flipv());
+    }
+    img
+}
+
+// the part of the image inside the crop, None if the crop is completely outside of it
+pub fn crop_image(img: &DynamicImage, crop: Option<Crop>) -> Option<Cow<'_, DynamicImage>> {
+    match crop {
+        Some(crop) => {
+            let (x, y, width, height) = crop.rect(img.width(), img.height())?;
+            Some(Cow::Owned(img.crop_imm(x, y, width, height)))
+        }
+        None => Some(Cow::Borrowed(img)),
+    }
+}
+
+// split an image into a grid of tiles, row by row from the top left.
+// the tiles on the right and bottom edges get the pixels that don't divide evenly
+pub fn split_tiles(img: &DynamicImage, columns: u32, rows: u32) -> Vec<DynamicImage> {
+    let (columns, rows) = (columns.clamp(1, img.width().max(1)), rows.clamp(1, img.height().max(1)));
+    let (tile_width, tile_height) = (img.width() / columns, img.height() / rows);
+    let mut tiles = Vec::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let (x, y) = (column * tile_width, row * tile_height);
+            let width = if column + 1 == columns { img.width() - x } else { tile_width };
+            let height = if row + 1 == rows { img.height() - y } else { tile_height };
+            tiles.push(img.crop_imm(x, y, width, height));
+        }
+    }
+    tiles
+}
+
+// only keep every n-th pixel in both directions
+fn sample(img: &DynamicImage, compress: u32) -> Cow<'_, DynamicImage> {
+    if compress <= 1 {
+        return Cow::Borrowed(img);
+    }
     let (width, height) = img.dimensions();
-    println!("Processing {}: {}x{}", filename, width, height);
+    let sampled = image::RgbaImage::from_fn(width.div_ceil(compress), height.div_ceil(compress), |x, y| {
+        img.get_pixel(x * compress, y * compress)
+    });
+    Cow::Owned(DynamicImage::ImageRgba8(sampled))
+}
+
+// turn every pixel into the index of the step it falls on, `index` picks the step for an intensity.
+// with dithering, the difference between a pixel and its step is pushed onto the pixels that come after it.
+// inverting counts the steps from the other end, the same way swap always has
+fn quantize_pixels(img: &DynamicImage, invert: bool, dither: bool, steps: usize, index: impl Fn(f32) -> usize) -> Vec<Vec<usize>> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let mut levels: Vec<Vec<f32>> = (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| intensity(img.get_pixel(x as u32, y as u32)))
+                .collect()
+        })
+        .collect();
+    let mut indices = vec![vec![0; width]; height];
     for y in 0..height {
-        // every 4*n rows
-        if y % (4 * compress) == 0 {
-            let mut asciiline = String::new();
-            for x in 0..width {
-                // every 2*n columns and if the coordinates aren't out of range
-                if x % (2 * compress) == 0
-                    && x + compress < width
-                    && y + compress < height
-                    && y + 2 * compress < height
-                    && y + 3 * compress < height
-                {
-                    let mut braille_bin = String::new();
-                    // get position of pixels in a 2x4 grid
-                    let pixelpos = [
-                        (x, y),
-                        (x + compress, y),
-                        (x, y + compress),
-                        (x + compress, y + compress),
-                        (x, y + 2 * compress),
-                        (x + compress, y + 2 * compress),
-                        (x, y + 3 * compress),
-                        (x + compress, y + 3 * compress),
-                    ];
-                    // follow the mapped value
-                    for i in braillemap {
-                        let (currentx, currenty) = pixelpos[i];
-                        let pixel = img.get_pixel(currentx, currenty);
-                        // NTSC intensity formula
-                        let intensity = pixel.0[0] as f32 * 0.299
-                            + pixel.0[1] as f32 * 0.587
-                            + pixel.0[2] as f32 * 0.114;
-                        // black pixel = 1
-                        if swap {
-                            if intensity < 127.5 {
-                                braille_bin = "0".to_string() + &braille_bin;
-                            // white pixel = 0
-                            } else {
-                                braille_bin = "1".to_string() + &braille_bin;
-                            }
-                        } else {
-                            if intensity < 127.5 {
-                                braille_bin = "1".to_string() + &braille_bin;
-                            // white pixel = 0
-                            } else {
-                                braille_bin = "0".to_string() + &braille_bin;
-                            }
+        for x in 0..width {
+            let i = index(levels[y][x].clamp(0., 255.));
+            indices[y][x] = if invert { steps - 1 - i } else { i };
+            if !dither || steps < 2 {
+                continue;
+            }
+            // Floyd-Steinberg: 7/16 right, 3/16 down left, 5/16 down, 1/16 down right
+            let error = levels[y][x] - i as f32 * 255. / (steps - 1) as f32;
+            let mut spread = |x: Option<usize>, y: usize, share: f32| {
+                if let Some(x) = x.filter(|&x| x < width && y < height) {
+                    levels[y][x] += error * share;
+                }
+            };
+            spread(Some(x + 1), y, 7. / 16.);
+            spread(x.checked_sub(1), y + 1, 3. / 16.);
+            spread(Some(x), y + 1, 5. / 16.);
+            spread(Some(x + 1), y + 1, 1. / 16.);
+        }
+    }
+    indices
+}
+
+// draw an image onto a canvas in any mode.
+// the cells are only colored if a color depth is set, so the same canvas works for plain text too.
+pub fn image_to_canvas(img: &DynamicImage, options: &RenderOptions) -> Canvas {
+    let turned = transform_image(img, options);
+    let compress = options.compress_for(turned.width());
+    let cropped = match crop_image(&turned, options.crop) {
+        Some(cropped) => cropped,
+        None => return Canvas::new(),
+    };
+    let img = sample(&cropped, compress);
+    let (width, height) = img.dimensions();
+    let colored = options.color.is_colored();
+    let threshold = options.threshold as f32;
+    // 0 is ink for braille and halfblock
+    let ink = |pixels: &[Vec<usize>], x: u32, y: u32| pixels[y as usize][x as usize] == 0;
+    let mut canvas = Canvas::new();
+    match options.mode {
+        Mode::Braille => {
+            let pixels = quantize_pixels(&img, options.invert, options.dither, 2, |level| (level.round() >= threshold) as usize);
+            // the bit of each dot, by its position in the 2x4 grid
+            let dots = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)];
+            // only take grids that fit in the image completely
+            for y in (0..height.saturating_sub(3)).step_by(4) {
+                let mut line = Vec::new();
+                for x in (0..width.saturating_sub(1)).step_by(2) {
+                    let mut bits = 0;
+                    let mut ink_pixels = Vec::new();
+                    for (bit, (dx, dy)) in dots.into_iter().enumerate() {
+                        if ink(&pixels, x + dx, y + dy) {
+                            bits |= 1 << bit;
+                            ink_pixels.push(img.get_pixel(x + dx, y + dy));
                         }
                     }
-                    asciiline += &bin_to_braille(&braille_bin, whitespace).to_string();
+                    let glyph = match bits {
+                        0 if options.whitespace => ' ',
+                        0 => '⡀',
+                        _ => char::from_u32(0x2800 + bits).unwrap(),
+                    }
+                    .to_string();
+                    if colored {
+                        // color the dots with the average of the pixels that are dots
+                        let all_pixels: Vec<_> = dots.iter().map(|&(dx, dy)| img.get_pixel(x + dx, y + dy)).collect();
+                        let color = average_color(if ink_pixels.is_empty() { &all_pixels } else { &ink_pixels });
+                        line.push(paint(glyph, color, None, options.color));
+                    } else {
+                        line.push(Cell::plain(glyph));
+                    }
+                }
+                canvas.push_row(line);
+            }
+        }
+        Mode::HalfBlock => {
+            let pixels = quantize_pixels(&img, options.invert, options.dither, 2, |level| (level.round() >= threshold) as usize);
+            // each cell is two pixels on top of each other
+            for y in (0..height).step_by(2) {
+                let mut line = Vec::new();
+                // the last row of an odd height image is its own bottom half
+                let bottom_y = if y + 1 < height { y + 1 } else { y };
+                for x in 0..width {
+                    if colored {
+                        let (top, bottom) = (img.get_pixel(x, y), img.get_pixel(x, bottom_y));
+                        line.push(paint("▀".to_string(), top, Some(bottom), options.color));
+                        continue;
+                    }
+                    let glyph = match (ink(&pixels, x, y), ink(&pixels, x, bottom_y)) {
+                        (true, true) => "█",
+                        (true, false) => "▀",
+                        (false, true) => "▄",
+                        (false, false) => " ",
+                    };
+                    line.push(Cell::plain(glyph));
                 }
+                canvas.push_row(line);
+            }
+        }
+        Mode::Block | Mode::Ascii => {
+            let ramp: Vec<&str> = match (&options.ramp, options.mode) {
+                (Some(ramp), _) => ramp.iter().map(String::as_str).collect(),
+                (None, Mode::Block) => BLOCK_RAMP.to_vec(),
+                (None, _) => ASCII_RAMP.to_vec(),
+            };
+            let pixels = if options.mode == Mode::Block && options.ramp.is_none() {
+                quantize_pixels(&img, options.invert, options.dither, ramp.len(), block_index)
+            } else {
+                quantize_pixels(&img, options.invert, options.dither, ramp.len(), |level| ramp_index(level, ramp.len()))
+            };
+            for y in 0..height {
+                let mut line = Vec::new();
+                for x in 0..width {
+                    let pixel = img.get_pixel(x, y);
+                    let glyph = if colored && options.mode == Mode::Block && options.ramp.is_none() {
+                        // colored blocks are solid, the color does the shading
+                        "█"
+                    } else {
+                        ramp[pixels[y as usize][x as usize]]
+                    }
+                    .repeat(options.cell_width());
+                    if colored {
+                        line.push(paint(glyph, pixel, None, options.color));
+                    } else {
+                        line.push(Cell::plain(glyph));
+                    }
+                }
+                canvas.push_row(line);
             }
-            final_ascii += &(asciiline.trim_end().to_owned() + "\n");
         }
     }
+    canvas
+}
+
+// make text safe to put inside html
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// any mode into any format: the bytes of the file and the width of the art in characters
+pub fn encode_image(
+    img: &DynamicImage,
+    renderer: &dyn Renderer,
+    encoder: &dyn Encoder,
+    options: &RenderOptions,
+) -> Result<(Vec<u8>, usize), String> {
+    let canvas = renderer.render(img, options);
+    // formats like json keep the name of whatever drew the art
+    let options = if options.renderer_name() == renderer.name() {
+        Cow::Borrowed(options)
+    } else {
+        Cow::Owned(RenderOptions { renderer: Some(renderer.name().to_string()), ..options.clone() })
+    };
+    Ok((encoder.encode(&canvas, &options)?, art_width(&canvas.to_text())))
+}
 
-    final_ascii
+// which files of a folder get converted
+#[derive(Default, Clone)]
+pub struct FileFilter {
+    // also go into subfolders
+    pub recursive: bool,
+    // only take files with these extensions. empty = every format the image crate can read
+    pub extensions: Vec<String>,
+    // only take files whose name matches one of these patterns. empty = every name
+    pub globs: Vec<String>,
 }
 
-// get file names in a given folder
+impl FileFilter {
+    // check a file's name against the extensions and patterns
+    pub fn matches(&self, path: &Path) -> bool {
+        let extension = match path.extension() {
+            Some(extension) => extension.to_string_lossy().to_lowercase(),
+            None => return false,
+        };
+        let extension_ok = if self.extensions.is_empty() {
+            image::ImageFormat::from_extension(&extension).is_some()
+        } else {
+            self.extensions
+                .iter()
+                .any(|wanted| wanted.trim_start_matches('.').to_lowercase() == extension)
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        extension_ok && (self.globs.is_empty() || self.globs.iter().any(|glob| glob_match(glob, &name)))
+    }
+}
+
+// simple wildcard matching, * is any amount of characters and ? is exactly one
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // where the last * was, and how much of the name it has eaten so far
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            // let the last * eat one more character and try again
+            star = Some((star_p, star_n + 1));
+            p = star_p + 1;
+            n = star_n + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// compare names the way a person would, so frame2 comes before frame10
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                // compare the whole number, ignoring leading zeros
+                let mut x_num = String::new();
+                while let Some(c) = a.next_if(char::is_ascii_digit) {
+                    x_num.push(c);
+                }
+                let mut y_num = String::new();
+                while let Some(c) = b.next_if(char::is_ascii_digit) {
+                    y_num.push(c);
+                }
+                let (x_trim, y_trim) = (x_num.trim_start_matches('0'), y_num.trim_start_matches('0'));
+                let ordering = x_trim
+                    .len()
+                    .cmp(&y_trim.len())
+                    .then_with(|| x_trim.cmp(y_trim))
+                    .then_with(|| x_num.len().cmp(&y_num.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+// get image file names in a given folder, in natural order
 pub fn get_files(folder: &str) -> Vec<String> {
+    find_files(folder, &FileFilter::default())
+}
+
+// get the file names in a folder that pass the filter, in natural order.
+// anything that can't be read is skipped with a warning.
+pub fn find_files(folder: &str, filter: &FileFilter) -> Vec<String> {
     let mut img_name = Vec::new();
-    let paths = fs::read_dir(folder).unwrap();
+    collect_files(Path::new(folder), filter, &mut img_name);
+    img_name.sort_by(|a, b| natural_cmp(a, b));
+    img_name
+}
+
+fn collect_files(folder: &Path, filter: &FileFilter, img_name: &mut Vec<String>) {
+    let paths = match fs::read_dir(folder) {
+        Ok(paths) => paths,
+        Err(e) => {
+            eprintln!("Skipping {}: {}", folder.display(), e);
+            return;
+        }
+    };
     for path in paths {
-        img_name.push(path.unwrap().path().display().to_string())
+        let path = match path {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                eprintln!("Skipping an entry of {}: {}", folder.display(), e);
+                continue;
+            }
+        };
+        if path.is_dir() {
+            if filter.recursive {
+                collect_files(&path, filter, img_name);
+            }
+        } else if filter.matches(&path) {
+            img_name.push(path.display().to_string())
+        }
     }
-    img_name
+}
+
+// add the <pre> tag so that html won't delete the spaces
+pub fn ascii_to_html(asciistring: &str) -> String {
+    format!("{}\n{}\n{}", "<pre>", asciistring, "</pre>")
 }
 
 // takes in a string of ascii art and creates a html file.
 pub fn write_html(name: &str, asciistring: String) {
-    // add the <pre> tag so that html won't delete the spaces
-    let html_string = format!("{}\n{}\n{}", "<pre>", asciistring, "</pre>");
-    // write the string into the .html file
     let filename = format!("{}.html", name);
-    let file = File::create(filename);
-    let _ = match file {
-        Ok(mut fileyes) => fileyes.write_all(html_string.as_bytes()),
-        Err(e) => panic!("write_html failed: {}", e),
-    };
+    if let Err(e) = create_file(&filename).and_then(|mut file| file.write_all(ascii_to_html(&asciistring).as_bytes())) {
+        panic!("write_html failed: {}", e)
+    }
 }
 
 // takes in a string of ascii art and creates a txt file.
 pub fn write_txt(name: &str, asciistring: String) {
     // write the string into the .txt file
     let filename = format!("{}.txt", name);
-    let file = File::create(filename);
-    let _ = match file {
-        Ok(mut fileyes) => fileyes.write_all(asciistring.as_bytes()),
-        Err(e) => panic!("write_txt failed: {}", e),
-    };
+    if let Err(e) = create_file(&filename).and_then(|mut file| file.write_all(asciistring.as_bytes())) {
+        panic!("write_txt failed: {}", e)
+    }
 }
 
-// Used to put files into a folder
-pub fn get_destination(filename: &Cow<'_, str>, output: &str) -> String {
-    // get file name without the folder path
-    let split_name = filename.split("\\").collect::<Vec<&str>>();
-    let nofoldname = split_name[split_name.len() - 1].split(".").collect::<Vec<&str>>();
-    // assign destination folder
-    format!("{}\\{}", output, &nofoldname[0])
+// creates a file, along with the folders it's in if they don't exist yet
+pub fn create_file(filename: &str) -> io::Result<File> {
+    if let Some(folder) = Path::new(filename).parent() {
+        fs::create_dir_all(folder)?;
+    }
+    File::create(filename)
 }
 
-// ===================================================== functions =====================================================
+// what to do when an output file already exists
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Overwrite {
+    // replace the old file
+    Replace,
+    // leave the old file alone and don't write anything
+    Skip,
+    // stop with an error
+    Fail,
+    // write to name_1.ext, name_2.ext, ... instead
+    Suffix,
+}
 
-// takes a folder and turns the entire folder into html or txt file and put it into the specified folder.
-pub fn imgfold2asciifold(
-    imgpath: &str,
-    asciipath: &str,
-    whitespace: bool,
-    chartype: &str,
-    filetype: &str,
-) {
-    for imagename in get_files(imgpath) {
-        let mut asciistring = String::new();
-        if chartype == "block" {
-            asciistring = img_to_asciistring(&imagename, 1, 2, false);
-        } else if chartype == "braille" {
-            asciistring = img_to_braillestring(&imagename, 1, whitespace, false);
+impl FromStr for Overwrite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overwrite" | "replace" => Ok(Overwrite::Replace),
+            "skip" => Ok(Overwrite::Skip),
+            "fail" => Ok(Overwrite::Fail),
+            "suffix" => Ok(Overwrite::Suffix),
+            _ => Err(format!("unknown overwrite policy '{}', expected overwrite, skip, fail or suffix", s)),
         }
-        // name = [output path][image name without path], no .html as write_html already adds the .html
-        let split_name = imagename.split("\\").collect::<Vec<&str>>();
-        let filename = format!("{}\\{}", asciipath, split_name[split_name.len() - 1]);
-        if filetype == "html" {
-            write_html(&filename, asciistring)
-        } else if filetype == "txt" {
-            write_txt(&filename, asciistring)
+    }
+}
+
+// where output files go and what they're called
+#[derive(Clone, Debug)]
+pub struct OutputNaming {
+    // the folder every output goes into
+    pub folder: PathBuf,
+    // write a single image's output to exactly this file instead of using the template
+    pub file: Option<PathBuf>,
+    // file name template. {stem} is the image name without extension, {name} is the image name
+    // with extension, {mode} is the mode (cblock for colored blocks), {width} is the width of the art in characters
+    // and {ext} is the output extension. tiles also have {row} and {column}
+    pub template: String,
+    pub overwrite: Overwrite,
+}
+
+impl Default for OutputNaming {
+    fn default() -> Self {
+        OutputNaming {
+            folder: PathBuf::from("output"),
+            file: None,
+            template: String::from("{stem}.{ext}"),
+            overwrite: Overwrite::Replace,
         }
     }
 }
 
-// compresses the image and prints it as ascii art.
-pub fn write_term(filename: &str, chartype: &str, whitespace: bool, colored: bool) {
-    let mut compress = 1;
-    let img = image::open(filename).expect("write_term failed!");
-    let (width, _) = img.dimensions();
-    if chartype == "braille" {
-        while width / compress >= 400 {
-            compress += 1;
+impl OutputNaming {
+    // decide between an output file and an output folder. existing folders, paths ending with a
+    // separator and paths without an extension are folders
+    // and "-" is stdout
+    pub fn with_output(mut self, output: &str) -> Self {
+        let path = PathBuf::from(output);
+        if output == "-" {
+            self.file = Some(path);
+        } else if path.is_dir() || output.ends_with(['/', '\\']) || path.extension().is_none() {
+            self.folder = path;
+            self.file = None;
+        } else {
+            self.file = Some(path);
         }
-        let asciistring = img_to_braillestring(&filename, compress, whitespace, true);
-        println!("{}", asciistring);
-    } else if chartype == "block" && colored {
-        while width / compress >= 67 {
-            compress += 1;
+        self
+    }
+
+    // the output path of an image. `imgpath` is the folder being converted, so that
+    // images in subfolders end up in the same subfolders of the output folder
+    pub fn destination(&self, imagename: &str, imgpath: Option<&str>, mode: &str, width: usize, ext: &str) -> PathBuf {
+        if let Some(file) = &self.file {
+            return file.clone();
         }
-        let block_vec = img_to_cblock(filename, compress);
-        for line in block_vec {
-            for char in line {
-                print!("{}", char)
-            }
-            println!("")
+        // stdin doesn't have a name
+        let image = Path::new(if imagename == "-" { "stdin" } else { imagename });
+        let stem = image.file_stem().unwrap_or_default().to_string_lossy();
+        let name = image.file_name().unwrap_or_default().to_string_lossy();
+        let filename = self
+            .template
+            .replace("{stem}", &stem)
+            .replace("{name}", &name)
+            .replace("{mode}", mode)
+            .replace("{width}", &width.to_string())
+            .replace("{ext}", ext);
+        let subfolder = imgpath
+            .and_then(|imgpath| image.strip_prefix(imgpath).ok())
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        self.folder.join(subfolder).join(filename)
+    }
+
+    // the output path of one tile of an image. the row and column (counting from 1) go where the template
+    // has {row} and {column}, or at the end of the name like cat_r2c3.txt
+    pub fn tile_destination(&self, imagename: &str, mode: &str, width: usize, ext: &str, row: usize, column: usize) -> PathBuf {
+        if self.file.is_none() && (self.template.contains("{row}") || self.template.contains("{column}")) {
+            let mut naming = self.clone();
+            naming.template = naming.template.replace("{row}", &row.to_string()).replace("{column}", &column.to_string());
+            return naming.destination(imagename, None, mode, width, ext);
         }
-    } else if chartype == "block" && !colored {
-        while width / compress >= 67 {
-            compress += 1;
+        let destination = self.destination(imagename, None, mode, width, ext);
+        if destination == Path::new("-") {
+            return destination;
         }
-        let asciistring = img_to_asciistring(&filename, compress, 3, true);
-        println!("{}", asciistring);
+        let stem = destination.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match destination.extension() {
+            Some(ext) => format!("{}_r{}c{}.{}", stem, row, column, ext.to_string_lossy()),
+            None => format!("{}_r{}c{}", stem, row, column),
+        };
+        destination.with_file_name(name)
     }
-}
 
-// print all compressed images in a folder into the terminal as ascii art
-pub fn imgfold2term(imgpath: &str, chartype: &str, whitespace: bool, colored: bool, msdelay: u64) {
-    let files = get_files(imgpath);
-    let mut compress = 1;
-    let img = image::open(&files[0]).expect("imgfold2term failed!");
-    let (width, _) = img.dimensions();
-    if chartype == "block" {
-        while width / compress >= 67 {
-            compress += 1;
+    // write the contents to the destination following the overwrite policy.
+    // returns where it was written, or None if it was skipped
+    pub fn write(&self, destination: &Path, contents: &[u8]) -> io::Result<Option<PathBuf>> {
+        if destination == Path::new("-") {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(contents)?;
+            stdout.flush()?;
+            return Ok(Some(destination.to_path_buf()));
         }
-    } else if chartype == "braille" {
-        while width / compress >= 400 {
-            compress += 1;
+        if let Some(folder) = destination.parent() {
+            fs::create_dir_all(folder)?;
         }
-    }
-    if chartype == "braille" || !colored {
-        let mut frames = Vec::new();
-        for imagename in files {
-            let mut asciistring = String::new();
-            if chartype == "block" {
-                asciistring = img_to_asciistring(&imagename, compress, 3, true);
-            } else if chartype == "braille" {
-                asciistring = img_to_braillestring(&imagename, compress, whitespace, true);
+        let (path, mut file) = match self.overwrite {
+            Overwrite::Replace => (destination.to_path_buf(), File::create(destination)?),
+            Overwrite::Fail => match File::create_new(destination) {
+                Ok(file) => (destination.to_path_buf(), file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    return Err(io::Error::new(e.kind(), "the file already exists"))
+                }
+                Err(e) => return Err(e),
+            },
+            Overwrite::Skip => match File::create_new(destination) {
+                Ok(file) => (destination.to_path_buf(), file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(None),
+                Err(e) => return Err(e),
+            },
+            Overwrite::Suffix => {
+                // create_new fails if the file is already there, so two workers can't grab the same name
+                let mut attempt = 0;
+                loop {
+                    let path = suffixed(destination, attempt);
+                    match File::create_new(&path) {
+                        Ok(file) => break (path, file),
+                        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                        Err(e) => return Err(e),
+                    }
+                }
             }
-            frames.push(asciistring);
+        };
+        file.write_all(contents)?;
+        Ok(Some(path))
+    }
+}
+
+// name.ext -> name_n.ext, n = 0 leaves the name alone
+fn suffixed(path: &Path, n: usize) -> PathBuf {
+    if n == 0 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let filename = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n),
+    };
+    path.with_file_name(filename)
+}
+
+// the width of a piece of ascii art in characters
+pub fn art_width(asciistring: &str) -> usize {
+    asciistring.lines().map(|line| line.chars().count()).max().unwrap_or(0)
+}
+
+// Used to put files into a folder
+#[allow(clippy::ptr_arg)]
+pub fn get_destination(filename: &Cow<'_, str>, output: &str) -> String {
+    // get file name without the folder path and extension
+    let nofoldname = Path::new(filename.as_ref()).file_stem().unwrap_or_default();
+    // assign destination folder
+    Path::new(output).join(nofoldname).display().to_string()
+}
+
+// check whether the input is a folder by looking at the file system instead of the name
+pub fn is_folder(path: &str) -> io::Result<bool> {
+    Ok(fs::metadata(path)?.is_dir())
+}
+
+// ===================================================== functions =====================================================
+
+// the number of worker threads to use when none is specified
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+// what happened to the files of a folder conversion
+pub struct BatchSummary {
+    pub succeeded: usize,
+    // where the outputs of the files that succeeded were written
+    pub written: Vec<PathBuf>,
+    // outputs that already existed and were left alone
+    pub skipped: usize,
+    // (file name, error message) of files that aren't images that can be read. they're skipped as well
+    pub unreadable: Vec<(String, String)>,
+    // (file name, error message)
+    pub failed: Vec<(String, String)>,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Converted {} files in {:.2}s: {} succeeded, {} skipped, {} failed",
+            self.succeeded + self.skipped + self.unreadable.len() + self.failed.len(),
+            self.elapsed.as_secs_f32(),
+            self.succeeded,
+            self.skipped + self.unreadable.len(),
+            self.failed.len()
+        )?;
+        for (imagename, error) in &self.unreadable {
+            writeln!(f, "    {}: skipped, {}", imagename, error)?;
         }
-        println!("Image loading complete, press Enter to begin playing");
-        let mut _buffer = String::new();
-        let _ = io::stdin().read_line(&mut _buffer);
-        for frame in frames {
-            let start_time = Instant::now();
-            println!("{frame}");
-            let end_time = Instant::now();
-            let duration = end_time.duration_since(start_time);
-            let processtime = duration.as_millis();
-            thread::sleep(Duration::from_millis(msdelay - processtime as u64))
+        for (imagename, error) in &self.failed {
+            writeln!(f, "    {}: {}", imagename, error)?;
         }
-    } else if chartype == "block" && colored {
-        let mut frames = Vec::new();
-        for imagename in files{
-            let block_vec = img_to_cblock(&imagename, compress);
-            frames.push(block_vec);
+        Ok(())
+    }
+}
+
+// what became of one file of a batch
+enum Outcome {
+    Written((u32, u32), PathBuf),
+    Exists,
+    Unreadable(String),
+    Failed(String),
+}
+
+// open every file and run `convert` on it using a pool of worker threads.
+// `convert` returns where the output was written (None if it was skipped), which is reported
+// in the same order as the files, no matter which worker finishes first. files that can't be
+// opened as images are skipped instead of failing the batch. 0 workers means one per core.
+pub fn batch_convert<F>(files: Vec<String>, workers: usize, convert: F) -> BatchSummary
+where
+    F: Fn(&str, &DynamicImage) -> Result<Option<PathBuf>, String> + Send + Sync + 'static,
+{
+    let start_time = Instant::now();
+    let workers = if workers == 0 { default_workers() } else { workers };
+    let names = files.clone();
+    let mut summary = BatchSummary {
+        succeeded: 0,
+        written: Vec::new(),
+        skipped: 0,
+        unreadable: Vec::new(),
+        failed: Vec::new(),
+        elapsed: Duration::ZERO,
+    };
+    let results = frame_pipeline(files, workers, 2 * workers, move |imagename| match open_upright(imagename) {
+        Ok(img) => match convert(imagename, &img) {
+            Ok(Some(destination)) => Outcome::Written(img.dimensions(), destination),
+            Ok(None) => Outcome::Exists,
+            Err(e) => Outcome::Failed(e),
+        },
+        Err(e) => Outcome::Unreadable(e.to_string()),
+    });
+    for (imagename, outcome) in names.into_iter().zip(results) {
+        match outcome {
+            Outcome::Written((width, height), destination) => {
+                status!("Processing {}: {}x{} -> {}", imagename, width, height, destination.display());
+                summary.succeeded += 1;
+                summary.written.push(destination);
+            }
+            Outcome::Exists => {
+                status!("Skipping {}: output already exists", imagename);
+                summary.skipped += 1;
+            }
+            Outcome::Unreadable(e) => {
+                status!("Skipping {}: {}", imagename, e);
+                summary.unreadable.push((imagename, e));
+            }
+            Outcome::Failed(e) => {
+                eprintln!("Failed {}: {}", imagename, e);
+                summary.failed.push((imagename, e));
+            }
         }
-        println!("Image loading complete, press Enter to begin playing");
-        let mut _buffer = String::new();
-        let _ = io::stdin().read_line(&mut _buffer);
-        for frame in frames {
-            let start_time = Instant::now();
-            for row in frame{
-                for pixel in row {
-                    print!("{pixel}")
+    }
+    summary.elapsed = start_time.elapsed();
+    summary
+}
+
+// takes a folder and turns the entire folder into files and put them into the specified folder.
+pub fn imgfold2asciifold(
+    imgpath: &str,
+    naming: &OutputNaming,
+    options: &RenderOptions,
+    format: OutputFormat,
+    filter: &FileFilter,
+    workers: usize,
+) -> BatchSummary {
+    imgfold2fold(imgpath, naming, Arc::new(options.mode), Arc::new(format), options, filter, workers)
+}
+
+// same as imgfold2asciifold, with any mode and format
+pub fn imgfold2fold(
+    imgpath: &str,
+    naming: &OutputNaming,
+    renderer: Arc<dyn Renderer>,
+    encoder: Arc<dyn Encoder>,
+    options: &RenderOptions,
+    filter: &FileFilter,
+    workers: usize,
+) -> BatchSummary {
+    files2fold(find_files(imgpath, filter), imgpath, naming, renderer, encoder, options, workers)
+}
+
+// same as imgfold2fold, for only some of the files of the folder, e.g. the ones that changed
+pub fn files2fold(
+    files: Vec<String>,
+    imgpath: &str,
+    naming: &OutputNaming,
+    renderer: Arc<dyn Renderer>,
+    encoder: Arc<dyn Encoder>,
+    options: &RenderOptions,
+    workers: usize,
+) -> BatchSummary {
+    let imgpath = imgpath.to_string();
+    let naming = naming.clone();
+    let options = options.clone();
+    let mode = renderer.mode_name(&options);
+    batch_convert(files, workers, move |imagename, img| {
+        let (contents, width) = encode_image(img, renderer.as_ref(), encoder.as_ref(), &options)?;
+        let destination = naming.destination(imagename, Some(&imgpath), &mode, width, encoder.extension());
+        naming.write(&destination, &contents).map_err(|e| e.to_string())
+    })
+}
+
+// compresses the image and prints it as ascii art.
+pub fn write_term(filename: &str, options: &RenderOptions) {
+    write_term_with(filename, &options.mode, options)
+}
+
+// same as write_term, with any mode
+pub fn write_term_with(filename: &str, renderer: &dyn Renderer, options: &RenderOptions) {
+    // only load once, stdin can't be read twice
+    let img = open_image(filename, "write_term");
+    renderer.render(&img, &options.for_term()).print();
+}
+
+// how many converted frames can wait in the buffer ahead of the player
+pub const FRAME_BUFFER: usize = 32;
+
+// frames coming out of the worker threads, in the same order as the files.
+// every worker has its own bounded channel and takes every n-th file,
+// so reading the channels in turn gives the frames back in order.
+pub struct FramePipeline<T> {
+    receivers: Vec<mpsc::Receiver<T>>,
+    next: usize,
+}
+
+impl<T> Iterator for FramePipeline<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let receiver = &self.receivers[self.next % self.receivers.len()];
+        self.next += 1;
+        // a worker only hangs up once it's out of files (or has crashed)
+        receiver.recv().ok()
+    }
+}
+
+// start worker threads that convert the files ahead of the consumer.
+// at most `buffer` converted frames are kept in memory at once.
+pub fn frame_pipeline<T, F>(files: Vec<String>, workers: usize, buffer: usize, convert: F) -> FramePipeline<T>
+where
+    T: Send + 'static,
+    F: Fn(&str) -> T + Send + Sync + 'static,
+{
+    let workers = workers.clamp(1, files.len().max(1));
+    let capacity = (buffer / workers).max(1);
+    let files = Arc::new(files);
+    let convert = Arc::new(convert);
+    let mut receivers = Vec::new();
+    for worker in 0..workers {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let files = Arc::clone(&files);
+        let convert = Arc::clone(&convert);
+        thread::spawn(move || {
+            for imagename in files.iter().skip(worker).step_by(workers) {
+                // the consumer stopped listening, no point converting the rest
+                if sender.send(convert(imagename)).is_err() {
+                    break;
                 }
-                println!("")
             }
-            let end_time = Instant::now();
-            let duration = end_time.duration_since(start_time);
-            let processtime = duration.as_millis();
-            thread::sleep(Duration::from_millis(msdelay - processtime as u64))
-        }
+        });
+        receivers.push(receiver);
     }
+    FramePipeline { receivers, next: 0 }
 }
 
-pub fn write_chtml(name: &str, output: &str){
-    let img = image::open(name).expect("write_chtml failed!");
-    let (width, height) = img.dimensions();
-    let mut final_string = String::from("<pre>\n");
-    println!("Processing {}: {}x{}", name, width, height);
-    for y in 0..height{
-        for x in 0..width{
-            let pixel: image::Rgba<u8> = img.get_pixel(x, y);
-            let (r, g, b) = (pixel.0[0], pixel.0[1], pixel.0[2]);
-            for _ in 0..2{
-                final_string += &format!("<font color='#{:02x}{:02x}{:02x}'>█</font>", r, g, b)
+// one frame of an animation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub canvas: Canvas,
+    // how long the frame stays up in milliseconds, None if it's up to the player
+    pub delay: Option<u64>,
+}
+
+// whether a file is a gif, which can have more than one frame
+pub fn is_gif(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+}
+
+// the frames of a folder of images or of an animated gif, drawn in the background while the earlier ones are used.
+// every frame is shrunk by the same factor, the one --width picks for the first image that can be read,
+// so images of other sizes still come out as art of other sizes.
+// images and frames that can't be loaded are skipped. json and bin cell grids come back the way they were saved
+// instead of being drawn again.
+pub fn frame_sequence(
+    imgpath: &str,
+    renderer: Arc<dyn Renderer>,
+    options: &RenderOptions,
+    filter: &FileFilter,
+) -> Result<Box<dyn Iterator<Item = Frame>>, String> {
+    if is_gif(imgpath) {
+        return gif_frames(imgpath, renderer, options);
+    }
+    if grid::is_grid(imgpath) {
+        return Ok(Box::new(grid::Grid::load(imgpath)?.frames.into_iter()));
+    }
+    let files = find_files(imgpath, filter);
+    // size everything after the first image that can be read
+    let (width, height) = files
+        .iter()
+        .find_map(|f| upright_dimensions(f).ok())
+        .ok_or_else(|| format!("No readable images found in {}", imgpath))?;
+    let mut options = options.clone();
+    options.size = Size::Compress(options.compress_for(options.rotated_size(width, height).0));
+    let frames = frame_pipeline(files, default_workers(), FRAME_BUFFER, move |imagename| {
+        let img = match open_upright(imagename) {
+            Ok(img) => img,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", imagename, e);
+                return None;
             }
+        };
+        Some(Frame { canvas: renderer.render(&img, &options), delay: None })
+    });
+    Ok(Box::new(frames.flatten()))
+}
+
+// the frames of an animated gif, with their own delays
+fn gif_frames(filename: &str, renderer: Arc<dyn Renderer>, options: &RenderOptions) -> Result<Box<dyn Iterator<Item = Frame>>, String> {
+    let file = File::open(filename).map_err(|e| format!("can't read {}: {}", filename, e))?;
+    let decoder = GifDecoder::new(io::BufReader::new(file)).map_err(|e| format!("can't read {}: {}", filename, e))?;
+    let (width, height) = decoder.dimensions();
+    let mut options = options.clone();
+    options.size = Size::Compress(options.compress_for(options.rotated_size(width, height).0));
+    let filename = filename.to_string();
+    let frames = decoder.into_frames().filter_map(move |frame| {
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                eprintln!("Skipping a frame of {}: {}", filename, e);
+                return None;
+            }
+        };
+        let delay = gif_delay(&frame);
+        let img = DynamicImage::ImageRgba8(frame.into_buffer());
+        Some(Frame { canvas: renderer.render(&img, &options), delay: Some(delay) })
+    });
+    Ok(Box::new(frames))
+}
+
+// how long a frame of a gif is up in milliseconds.
+// browsers show gifs with a delay of 10ms or less at 100ms, so they look the same here
+fn gif_delay(frame: &image::Frame) -> u64 {
+    let (numer, denom) = frame.delay().numer_denom_ms();
+    match numer as u64 / denom.max(1) as u64 {
+        0..=10 => 100,
+        delay => delay,
+    }
+}
+
+// the delays of every frame of a gif, the way they're played
+pub fn gif_delays(filename: &str) -> Result<Vec<u64>, String> {
+    let file = File::open(filename).map_err(|e| format!("can't read {}: {}", filename, e))?;
+    let decoder = GifDecoder::new(io::BufReader::new(file)).map_err(|e| format!("can't read {}: {}", filename, e))?;
+    decoder
+        .into_frames()
+        .map(|frame| frame.map(|frame| gif_delay(&frame)).map_err(|e| format!("can't read {}: {}", filename, e)))
+        .collect()
+}
+
+// print all compressed images in a folder, or the frames of an animated gif, into the terminal as ascii art.
+// frames are converted in the background while the earlier ones are playing.
+// frames that can't be loaded are skipped.
+pub fn imgfold2term(imgpath: &str, options: &RenderOptions, msdelay: u64, filter: &FileFilter) {
+    imgfold2term_with(imgpath, Arc::new(options.mode), options, msdelay, filter)
+}
+
+// same as imgfold2term, with any mode
+pub fn imgfold2term_with(imgpath: &str, renderer: Arc<dyn Renderer>, options: &RenderOptions, msdelay: u64, filter: &FileFilter) {
+    let frames = match frame_sequence(imgpath, renderer, &options.for_term(), filter) {
+        Ok(frames) => frames,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
         }
-        final_string += "\n"
+    };
+    // the first frames are converted in the background while waiting
+    eprintln!("Image loading started, press Enter to begin playing");
+    let mut _buffer = String::new();
+    let _ = io::stdin().read_line(&mut _buffer);
+    for frame in frames {
+        let start_time = Instant::now();
+        frame.canvas.print();
+        let processtime = start_time.elapsed().as_millis() as u64;
+        thread::sleep(Duration::from_millis(frame.delay.unwrap_or(msdelay).saturating_sub(processtime)))
     }
-    final_string += "</pre>";
-    let filename = format!("{output}.html");
-    let file = File::create(filename);
-    let _ = match file {
-        Ok(mut fileyes) => fileyes.write_all(final_string.as_bytes()),
-        Err(e) => panic!("write_html failed: {}", e),
+}
+
+// turn all images in a folder, or the frames of an animated gif, into one html page that plays them.
+// returns the page and the width of the art in characters
+pub fn imgfold2player(
+    imgpath: &str,
+    renderer: Arc<dyn Renderer>,
+    options: &RenderOptions,
+    msdelay: u64,
+    filter: &FileFilter,
+) -> Result<(String, usize), String> {
+    let frames: Vec<Frame> = frame_sequence(imgpath, renderer, options, filter)?.collect();
+    if frames.is_empty() {
+        return Err(format!("No frames could be read from {}", imgpath));
+    }
+    let width = frames.iter().map(|frame| art_width(&frame.canvas.to_text())).max().unwrap_or(0);
+    Ok((html::player(&frames, msdelay), width))
+}
+
+// same as imgfold2player, as an animated gif drawn with the font of `raster`.
+// gifs keep how often they loop, folders loop forever
+pub fn imgfold2gif(
+    imgpath: &str,
+    renderer: Arc<dyn Renderer>,
+    raster: &RasterEncoder,
+    options: &RenderOptions,
+    msdelay: u64,
+    filter: &FileFilter,
+) -> Result<(Vec<u8>, usize), String> {
+    let frames: Vec<Frame> = frame_sequence(imgpath, renderer, options, filter)?.collect();
+    if frames.is_empty() {
+        return Err(format!("No frames could be read from {}", imgpath));
+    }
+    let repeat = if is_gif(imgpath) { gif_repeat(imgpath) } else { Repeat::Infinite };
+    let width = frames.iter().map(|frame| art_width(&frame.canvas.to_text())).max().unwrap_or(0);
+    Ok((raster.animation(&frames, msdelay, repeat)?, width))
+}
+
+// same as imgfold2player, as an asciicast v2 recording of what `imgfold2term` prints.
+// a single image that isn't a gif becomes a recording of `write_term`, with one frame
+pub fn imgfold2cast(
+    imgpath: &str,
+    renderer: Arc<dyn Renderer>,
+    options: &RenderOptions,
+    msdelay: u64,
+    filter: &FileFilter,
+) -> Result<(String, usize), String> {
+    let options = options.for_term();
+    let frames: Vec<Frame> = if Path::new(imgpath).is_dir() || is_gif(imgpath) || grid::is_grid(imgpath) {
+        frame_sequence(imgpath, renderer, &options, filter)?.collect()
+    } else {
+        let img = load_image(imgpath).map_err(|e| format!("can't read {}: {}", imgpath, e))?;
+        vec![Frame { canvas: renderer.render(&img, &options), delay: None }]
     };
+    if frames.is_empty() {
+        return Err(format!("No frames could be read from {}", imgpath));
+    }
+    let width = frames.iter().map(|frame| art_width(&frame.canvas.to_text())).max().unwrap_or(0);
+    Ok((cast::recording(&frames, msdelay, imgpath), width))
+}
+
+// same as imgfold2player, as a json or bin cell grid. frames without their own delay get `msdelay`
+pub fn imgfold2grid(
+    imgpath: &str,
+    renderer: Arc<dyn Renderer>,
+    options: &RenderOptions,
+    msdelay: u64,
+    filter: &FileFilter,
+    binary: bool,
+) -> Result<(Vec<u8>, usize), String> {
+    let mode = renderer.name().to_string();
+    let frames: Vec<Frame> = frame_sequence(imgpath, renderer, options, filter)?
+        .map(|frame| Frame { delay: Some(frame.delay.unwrap_or(msdelay)), ..frame })
+        .collect();
+    if frames.is_empty() {
+        return Err(format!("No frames could be read from {}", imgpath));
+    }
+    let grid = grid::Grid::new(&mode, frames);
+    let width = grid.frames.iter().map(|frame| art_width(&frame.canvas.to_text())).max().unwrap_or(0);
+    Ok((if binary { grid.to_bin() } else { grid.to_json().into_bytes() }, width))
 }
 
-pub fn imgfold2chtml(imgpath: &str, asciipath: &str){
-    let files = get_files(imgpath);
-    println!("{files:?}");
-    for imagename in files{
-        let split_name = imagename.split("\\").collect::<Vec<&str>>();
-        let filename = format!("{}\\{}", asciipath, split_name[split_name.len() - 1]);
-        write_chtml(&imagename, &filename)
+// how often a gif plays, out of its NETSCAPE2.0 block. gifs without one play once
+fn gif_repeat(filename: &str) -> Repeat {
+    let bytes = fs::read(filename).unwrap_or_default();
+    let block = bytes.windows(11).position(|window| window == b"NETSCAPE2.0");
+    // the block is followed by a sub-block of 3 bytes: 1 and the loop count, 0 meaning forever
+    match block.and_then(|i| bytes.get(i + 11..i + 15)) {
+        Some(&[3, 1, low, high]) => match u16::from_le_bytes([low, high]) {
+            0 => Repeat::Infinite,
+            count => Repeat::Finite(count),
+        },
+        _ => Repeat::Finite(0),
     }
-}
\ No newline at end of file
+}
+
+pub fn write_chtml(name: &str, output: &str){
+    let filename = format!("{output}.html");
+    if let Err(e) = create_file(&filename).and_then(|mut file| file.write_all(img_to_chtml(name).as_bytes())) {
+        panic!("write_html failed: {}", e)
+    }
+}
+
+// creates the colored html string of an image file
+pub fn img_to_chtml(filename: &str) -> String {
+    let img = open_image(filename, "img_to_chtml");
+    image_to_chtml(&img)
+}
+
+// creates the colored html string of an image that is already loaded
+pub fn image_to_chtml(img: &DynamicImage) -> String {
+    let options = RenderOptions::new(Mode::Block).color(ColorDepth::TrueColor);
+    image_to_canvas(img, &options).to_html()
+}
+
+pub fn imgfold2chtml(imgpath: &str, naming: &OutputNaming, filter: &FileFilter, workers: usize) -> BatchSummary {
+    let options = RenderOptions::new(Mode::Block).color(ColorDepth::TrueColor);
+    imgfold2asciifold(imgpath, naming, &options, OutputFormat::Html, filter, workers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn natural_cmp_sorts_numbers_by_value() {
+        let mut names = vec!["frame10.png", "frame2.png", "Frame1.png", "frame02.png", "frame.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, ["frame.png", "Frame1.png", "frame2.png", "frame02.png", "frame10.png"]);
+        assert_eq!(natural_cmp("a9b", "a10a"), Ordering::Less);
+        assert_eq!(natural_cmp("IMG", "img"), Ordering::Equal);
+    }
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("*.png", "frame1.png"));
+        assert!(!glob_match("*.png", "frame1.jpg"));
+        assert!(glob_match("frame?.png", "frame1.png"));
+        assert!(!glob_match("frame?.png", "frame10.png"));
+        assert!(glob_match("f*e*0.*", "frame10.png"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn overwrite_policy_names() {
+        assert_eq!("overwrite".parse(), Ok(Overwrite::Replace));
+        assert_eq!("replace".parse(), Ok(Overwrite::Replace));
+        assert_eq!("skip".parse(), Ok(Overwrite::Skip));
+        assert_eq!("fail".parse(), Ok(Overwrite::Fail));
+        assert_eq!("suffix".parse(), Ok(Overwrite::Suffix));
+        assert!("keep".parse::<Overwrite>().is_err());
+    }
+
+    #[test]
+    fn output_naming_templates() {
+        let naming = OutputNaming { template: String::from("{stem}_{mode}_{width}.{ext}"), ..Default::default() };
+        let naming = naming.with_output("out");
+        assert_eq!(naming.destination("photos/cat.jpg", None, "block", 80, "txt"), Path::new("out/cat_block_80.txt"));
+        assert_eq!(naming.destination("photos/trip/cat.jpg", Some("photos"), "block", 80, "txt"), Path::new("out/trip/cat_block_80.txt"));
+        assert_eq!(naming.destination("-", None, "ascii", 40, "txt"), Path::new("out/stdin_ascii_40.txt"));
+        assert_eq!(naming.tile_destination("cat.jpg", "block", 20, "txt", 2, 3), Path::new("out/cat_block_20_r2c3.txt"));
+        let naming = OutputNaming { template: String::from("{name}-{row}x{column}.{ext}"), ..naming };
+        assert_eq!(naming.tile_destination("cat.jpg", "block", 20, "txt", 2, 3), Path::new("out/cat.jpg-2x3.txt"));
+        let naming = naming.with_output("art.html");
+        assert_eq!(naming.destination("photos/cat.jpg", None, "block", 80, "html"), Path::new("art.html"));
+    }
+
+    #[test]
+    fn overwrite_policies() {
+        let folder = std::env::temp_dir().join(format!("img_to_ascii_overwrite_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        let destination = folder.join("cat.txt");
+        let naming = |overwrite| OutputNaming { overwrite, ..Default::default() };
+        assert_eq!(naming(Overwrite::Fail).write(&destination, b"1").unwrap(), Some(destination.clone()));
+        assert!(naming(Overwrite::Fail).write(&destination, b"2").is_err());
+        assert_eq!(naming(Overwrite::Skip).write(&destination, b"3").unwrap(), None);
+        assert_eq!(fs::read(&destination).unwrap(), b"1");
+        assert_eq!(naming(Overwrite::Suffix).write(&destination, b"4").unwrap(), Some(folder.join("cat_1.txt")));
+        assert_eq!(naming(Overwrite::Suffix).write(&destination, b"5").unwrap(), Some(folder.join("cat_2.txt")));
+        assert_eq!(naming(Overwrite::Replace).write(&destination, b"6").unwrap(), Some(destination.clone()));
+        assert_eq!(fs::read(&destination).unwrap(), b"6");
+        assert_eq!(fs::read(folder.join("cat_2.txt")).unwrap(), b"5");
+        fs::remove_dir_all(&folder).unwrap();
+    }
+}
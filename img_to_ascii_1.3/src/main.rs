@@ -1,151 +1,1088 @@
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use img_to_ascii::ansi::{AnsiEncoder, Sauce};
+use img_to_ascii::chat::MarkdownEncoder;
+use img_to_ascii::config::{Config, Settings, Value};
+use img_to_ascii::grid::{is_grid, Grid};
+use img_to_ascii::raster::{BitmapFont, RasterEncoder, RasterFormat};
+use img_to_ascii::svg::SvgEncoder;
 use img_to_ascii::*;
+use img_to_ascii::watch::watch;
+use std::{collections::HashSet, fs, path::Path, sync::Arc};
+
+// options that are switched on just by being there
+const FLAGS: [&str; 12] = [
+    "quiet",
+    "color",
+    "whitespace",
+    "pad",
+    "dither",
+    "invert",
+    "flip-horizontal",
+    "flip-vertical",
+    "recursive",
+    "watch",
+    "cp437",
+    "sauce",
+];
+// options that take a single value
+const VALUES: [&str; 26] = [
+    "mode",
+    "color-depth",
+    "width",
+    "compress",
+    "ramp",
+    "threshold",
+    "rotate",
+    "crop",
+    "format",
+    "animation-format",
+    "sauce-title",
+    "sauce-author",
+    "sauce-group",
+    "font",
+    "font-size",
+    "line-height",
+    "bitmap-font",
+    "scale",
+    "max-width",
+    "tiles",
+    "output",
+    "name",
+    "overwrite",
+    "jobs",
+    "delay",
+    "address",
+];
+// options that take a list of values
+const LISTS: [&str; 2] = ["ext", "glob"];
 
 fn main() {
-    // Define the command line application and its arguments using Clap
+    // --no-<flag> switches off a flag that the config file switches on
+    let negated: Vec<String> = FLAGS.iter().map(|flag| format!("no-{}", flag)).collect();
+    // the modes and formats that can be picked by name
+    let registry = Registry::default();
+    // Define the command line application and its subcommands using Clap
     let matches = App::new("img_to_ascii")
         .version("1.3")
         .author("Kongfah Sangchaisirisak")
         .about("An image to ASCII conversion program.")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
         .arg(
-            Arg::with_name("filename")
-                .value_name("filename")
-                .help("Input the name of the image file in JPG or PNG")
-                .index(1)
-                .required(true)
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .global(true)
+                .help("Doesn't print progress messages. They go to stderr either way, so stdout only has the art in it"),
         )
         .arg(
-            Arg::with_name("block")
-                .short("l")
-                .long("block")
-                .help("Creates ASCII art using block elements")
-                .conflicts_with("braille"),
+            Arg::with_name("preset")
+                .short("p")
+                .long("preset")
+                .value_name("NAME")
+                .takes_value(true)
+                .global(true)
+                .help("Uses the settings of a [preset.<NAME>] table from the config file. Options on the command line still win"),
         )
         .arg(
-            Arg::with_name("braille")
-                .short("r")
-                .long("braille")
-                .help("Creates ASCII art using braille characters")
+            Arg::with_name("config")
+                .long("config")
+                .value_name("FILE")
+                .takes_value(true)
+                .global(true)
+                .help("Reads the settings from this file instead of ./img_to_ascii.toml and $XDG_CONFIG_HOME/img_to_ascii/config.toml. Flags it switches on can be switched off again with --no-<flag>, e.g. --no-color"),
         )
-        .arg(
-            Arg::with_name("html")
-                .short("h")
-                .long("html")
-                .help("Outputs the file as an html file")
-                .conflicts_with("text"),
+        .args(&negated.iter().map(|name| Arg::with_name(name).long(name).global(true).hidden(true)).collect::<Vec<_>>())
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about("Converts a single image into a file, or prints it into the terminal if no format is given")
+                .arg(input_arg("Input the name of the image file in JPG or PNG, or - to read the image from stdin"))
+                .args(&render_args(&registry))
+                .arg(format_arg(&registry))
+                .args(&ansi_args())
+                .args(&svg_args())
+                .args(&raster_args())
+                .arg(max_width_arg())
+                .arg(
+                    Arg::with_name("tiles")
+                        .long("tiles")
+                        .value_name("COLUMNSxROWS")
+                        .takes_value(true)
+                        .help("Splits the image into a grid of tiles, e.g. 3x2, and writes every tile into its own file named like name_r1c2.ext"),
+                )
+                .args(&output_args())
+                .arg(watch_arg("Keeps running and converts the images again whenever they're added or changed, until stopped with ctrl-c")),
         )
-        .arg(
-            Arg::with_name("text")
-                .short("t")
-                .long("text")
-                .help("Outputs the file as a txt file"),
+        .subcommand(
+            SubCommand::with_name("batch")
+                .about("Converts every image in a folder into files")
+                .arg(input_arg("Input the name of the folder"))
+                .args(&render_args(&registry))
+                .arg(format_arg(&registry))
+                .args(&ansi_args())
+                .args(&svg_args())
+                .args(&raster_args())
+                .arg(max_width_arg())
+                .args(&output_args())
+                .arg(watch_arg("Keeps running and converts the images again whenever they're added or changed, until stopped with ctrl-c"))
+                .args(&folder_args())
+                .arg(jobs_arg("Number of images to convert at the same time. Defaults to the number of cores")),
         )
-        .arg(
-            Arg::with_name("whitespace")
-                .short("w")
-                .long("whitespace")
-                .help("Uses whitespaces for empty pixels for braille ASCII art. Does not do anything for block.\nWarning: may cause misalignment"),
+        .subcommand(
+            SubCommand::with_name("play")
+                .about("Prints an image into the terminal, or plays every image in a folder or an animated GIF as an animation")
+                .arg(input_arg("Input the name of the image file, GIF or folder, or - to read the image from stdin"))
+                .args(&render_args(&registry))
+                .args(&folder_args())
+                .arg(delay_arg())
+                .arg(watch_arg("Keeps running and draws the images again whenever they're added or changed, instead of playing them, until stopped with ctrl-c")),
         )
-        .arg(
-            Arg::with_name("colored")
-                .short("c")
-                .long("colored")
-                .help("Colors html and terminal output. Does not do anything for braille and txt output.\nWarning: will make the conversions slower"),
+        .subcommand(
+            SubCommand::with_name("animate")
+                .about("Saves every image in a folder, or the frames of an animated GIF, as one html page that plays them, an animated GIF or an asciinema recording")
+                .arg(input_arg("Input the name of the folder or GIF"))
+                .args(&render_args(&registry))
+                .arg(
+                    Arg::with_name("animation-format")
+                        .short("f")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["html", "gif", "cast", "json", "bin"])
+                        .help("An html page with a player, an animated GIF drawn with a bitmap font, an asciicast v2 recording of playing it in the terminal for asciinema, or the cells of every frame as json or bin. Defaults to html"),
+                )
+                .args(&raster_args())
+                .args(&output_args())
+                .args(&folder_args())
+                .arg(delay_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("info")
+                .about("Prints the size and color type of an image, the frames of an animation, and how big the art of every mode comes out")
+                .arg(input_arg("Input the name of the image file, GIF, folder or cell grid, or - to read the image from stdin"))
+                .args(&render_args(&registry))
+                .args(&folder_args())
+                .arg(delay_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Runs an http server that converts images POSTed to /convert")
+                .arg(
+                    Arg::with_name("address")
+                        .short("a")
+                        .long("address")
+                        .value_name("HOST:PORT")
+                        .takes_value(true)
+                        .help("Where to listen. Defaults to 127.0.0.1:8080"),
+                )
+                .arg(jobs_arg("Number of requests to answer at the same time. Defaults to the number of cores")),
+        )
+        .subcommand(
+            SubCommand::with_name("config")
+                .about("Works with the config file")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("show")
+                        .about("Prints the settings that are used when nothing is given on the command line"),
+                ),
         )
         .get_matches();
-    
-    if !(matches.is_present("block") || matches.is_present("braille")){
-        eprintln!(
-            "error: --braille or --block is required
-
-USAGE:
-    img_to_ascii.exe <filename> --block
-    OR
-    img_to_ascii.exe <filename> --braille"
-        );
+
+    let (name, matches) = matches.subcommand();
+    let options = Options::new(matches.unwrap(), &registry);
+    set_quiet(options.flag("quiet"));
+    match name {
+        "convert" => convert(&options),
+        "batch" => batch(&options),
+        "play" => play(&options),
+        "animate" => animate(&options),
+        "info" => info(&options),
+        "serve" => {
+            if let Err(e) = serve::serve(&options.value("address").unwrap(), registry.clone(), workers(&options)) {
+                fail(&format!("can't serve: {}", e));
+            }
+        }
+        "config" => config_show(&options),
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+// ===================================================== options =====================================================
+
+// the built-in settings, used when neither the command line nor the config file has something
+fn builtin_settings() -> Settings {
+    let mut settings = Settings::new();
+    for flag in FLAGS {
+        settings.insert(flag.to_string(), Value::Boolean(false));
+    }
+    for (key, value) in [
+        ("mode", "block"),
+        ("output", "output"),
+        ("name", "{stem}.{ext}"),
+        ("overwrite", "overwrite"),
+        ("address", "127.0.0.1:8080"),
+        ("animation-format", "html"),
+    ] {
+        settings.insert(key.to_string(), Value::String(value.to_string()));
+    }
+    settings.insert(String::from("threshold"), Value::Integer(128));
+    settings.insert(String::from("jobs"), Value::Integer(default_workers() as i64));
+    settings.insert(String::from("delay"), Value::Integer(200));
+    for list in LISTS {
+        settings.insert(list.to_string(), Value::Array(Vec::new()));
+    }
+    settings
+}
+
+// the options of a subcommand. the command line wins over the config file, which wins over the built-in settings
+struct Options<'a> {
+    matches: &'a ArgMatches<'a>,
+    registry: &'a Registry,
+    // what the config file and preset say
+    config: Settings,
+    sources: Vec<std::path::PathBuf>,
+    preset: Option<String>,
+}
+
+impl<'a> Options<'a> {
+    fn new(matches: &'a ArgMatches<'a>, registry: &'a Registry) -> Self {
+        let loaded = match matches.value_of("config") {
+            Some(path) => {
+                let mut config = Config::default();
+                config.load_file(Path::new(path)).map(|_| config)
+            }
+            None => Config::load(),
+        };
+        let loaded = loaded.unwrap_or_else(|e| fail(&e));
+        let preset = matches.value_of("preset").map(String::from);
+        let config = loaded.settings(preset.as_deref()).unwrap_or_else(|e| fail(&e));
+        // catch typos and wrong types early instead of ignoring them
+        for (key, value) in &config {
+            let ok = if FLAGS.contains(&key.as_str()) {
+                value.is_bool()
+            } else if VALUES.contains(&key.as_str()) {
+                value.is_str() || value.is_integer() || value.is_float()
+            } else if LISTS.contains(&key.as_str()) {
+                value.is_str() || value.as_array().is_some_and(|values| values.iter().all(Value::is_str))
+            } else {
+                fail(&format!("unknown config key '{}'", key))
+            };
+            if !ok {
+                fail(&format!("config key '{}' has the wrong type: {}", key, value));
+            }
+        }
+        Options { matches, registry, config, sources: loaded.sources, preset }
+    }
+
+    // the value of a single-value option
+    fn value(&self, key: &str) -> Option<String> {
+        if self.matches.occurrences_of(key) > 0 {
+            return self.matches.value_of(key).map(String::from);
+        }
+        match self.config.get(key).or(builtin_settings().get(key)) {
+            Some(Value::String(value)) => Some(value.clone()),
+            Some(value) => Some(value.to_string()),
+            None => None,
+        }
+    }
+
+    // whether a flag is on. if both --<flag> and --no-<flag> are given the last one wins
+    fn flag(&self, key: &str) -> bool {
+        let on = self.matches.index_of(key);
+        let off = self.matches.index_of(format!("no-{}", key));
+        match (on, off) {
+            (Some(on), Some(off)) => on > off,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => self.config.get(key).and_then(Value::as_bool).unwrap_or(false),
+        }
+    }
+
+    // the values of a list option
+    fn values(&self, key: &str) -> Vec<String> {
+        if let Some(values) = self.matches.values_of(key) {
+            return values.map(String::from).collect();
+        }
+        match self.config.get(key) {
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).map(String::from).collect(),
+            Some(Value::String(value)) => value.split(',').map(String::from).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // whether the option was given on the command line or in the config
+    fn is_set(&self, key: &str) -> bool {
+        self.matches.occurrences_of(key) > 0 || self.config.contains_key(key)
+    }
+
+    fn filename(&self) -> String {
+        self.matches.value_of("filename").unwrap().to_string()
+    }
+}
+
+// ===================================================== arguments =====================================================
+
+fn input_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("filename")
+        .value_name("filename")
+        .help(help)
+        .index(1)
+        .required(true)
+}
+
+// how the art looks
+fn render_args(registry: &Registry) -> Vec<Arg<'static, '_>> {
+    vec![
+        Arg::with_name("mode")
+            .short("m")
+            .long("mode")
+            .value_name("MODE")
+            .takes_value(true)
+            .possible_values(&registry.renderer_names())
+            .help("What the art is made of: block elements, braille characters, ascii characters or half blocks. Defaults to block"),
+        Arg::with_name("color")
+            .short("c")
+            .long("color")
+            .alias("colored")
+            .help("Colors html and terminal output in truecolor.\nWarning: will make the conversions slower"),
+        Arg::with_name("color-depth")
+            .long("color-depth")
+            .value_name("DEPTH")
+            .takes_value(true)
+            .possible_values(&ColorDepth::ALL.map(ColorDepth::name))
+            .help("How many colors to use, for terminals that can't show every color. Any depth but mono turns on --color"),
+        Arg::with_name("width")
+            .long("width")
+            .value_name("COLUMNS")
+            .takes_value(true)
+            .conflicts_with("compress")
+            .help("Shrinks the art to at most this many characters wide. The terminal defaults to 200, files to the full image"),
+        Arg::with_name("compress")
+            .long("compress")
+            .value_name("N")
+            .takes_value(true)
+            .help("Only uses every N-th pixel of the image"),
+        Arg::with_name("ramp")
+            .long("ramp")
+            .value_name("CHARACTERS")
+            .takes_value(true)
+            .help("Characters from the darkest pixel to the brightest for block and ascii art, e.g. --ramp '@#+. '"),
+        Arg::with_name("threshold")
+            .long("threshold")
+            .value_name("0-255")
+            .takes_value(true)
+            .help("Pixels darker than this become dots in braille and ink in halfblock art. Defaults to 128"),
+        Arg::with_name("rotate")
+            .long("rotate")
+            .value_name("DEGREES")
+            .takes_value(true)
+            .possible_values(&["0", "90", "180", "270"])
+            .help("Turns the image clockwise before drawing it. Photos are already turned upright by their exif orientation"),
+        Arg::with_name("flip-horizontal")
+            .long("flip-horizontal")
+            .help("Mirrors the image left to right, after --rotate"),
+        Arg::with_name("flip-vertical")
+            .long("flip-vertical")
+            .help("Mirrors the image top to bottom, after --rotate"),
+        Arg::with_name("crop")
+            .long("crop")
+            .value_name("GEOMETRY")
+            .takes_value(true)
+            .help("Only turns part of the image into art, as WIDTHxHEIGHT+X+Y in pixels or percent of the turned image, e.g. 640x480+100+50 or 50%x50%+25%+25%"),
+        Arg::with_name("dither")
+            .long("dither")
+            .help("Spreads the rounding error to the neighbouring pixels, so gradients don't turn into bands"),
+        Arg::with_name("invert")
+            .long("invert")
+            .help("Swaps dark and bright"),
+        Arg::with_name("whitespace")
+            .short("w")
+            .long("whitespace")
+            .help("Uses whitespaces for empty pixels for braille ASCII art. Does not do anything for other modes.\nWarning: may cause misalignment, spaces aren't as wide as braille in every font"),
+        Arg::with_name("pad")
+            .long("pad")
+            .help("Fills every line of txt and markdown files up to the same width instead of trimming the ends, with empty braille for braille and spaces for the other modes. Fails if a character isn't exactly one column wide"),
+    ]
+}
+
+fn format_arg(registry: &Registry) -> Arg<'static, '_> {
+    Arg::with_name("format")
+        .short("f")
+        .long("format")
+        .value_name("FORMAT")
+        .takes_value(true)
+        .possible_values(&registry.encoder_names())
+        .help("The type of file to write")
+}
+
+// only used by the ansi format
+fn ansi_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("cp437")
+            .long("cp437")
+            .help("Writes ansi files in code page 437 with dos line endings, for ansi art tools. Characters it doesn't have, like braille, become ?"),
+        Arg::with_name("sauce")
+            .long("sauce")
+            .help("Adds a SAUCE record with the size of the art to the end of ansi files"),
+        Arg::with_name("sauce-title")
+            .long("sauce-title")
+            .value_name("TITLE")
+            .takes_value(true)
+            .help("The title in the SAUCE record, turns on --sauce"),
+        Arg::with_name("sauce-author")
+            .long("sauce-author")
+            .value_name("NAME")
+            .takes_value(true)
+            .help("The author in the SAUCE record, turns on --sauce"),
+        Arg::with_name("sauce-group")
+            .long("sauce-group")
+            .value_name("NAME")
+            .takes_value(true)
+            .help("The group in the SAUCE record, turns on --sauce"),
+    ]
+}
+
+// only used by the svg format
+fn svg_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("font")
+            .long("font")
+            .value_name("FAMILY")
+            .takes_value(true)
+            .help("The font of svg files, as a css font-family. Has to be monospace. Defaults to 'DejaVu Sans Mono', Menlo, Consolas, monospace"),
+        Arg::with_name("font-size")
+            .long("font-size")
+            .value_name("PX")
+            .takes_value(true)
+            .help("The font size of svg files in pixels. Defaults to 12"),
+        Arg::with_name("line-height")
+            .long("line-height")
+            .value_name("FACTOR")
+            .takes_value(true)
+            .help("How tall a row of svg files is, compared to the font size. Defaults to 1"),
+    ]
+}
+
+// only used by the png and jpeg formats, and gif animations
+fn raster_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("bitmap-font")
+            .long("bitmap-font")
+            .value_name("FILE")
+            .takes_value(true)
+            .help("Draws png, jpeg and gif files with this BDF or PSF font instead of the built-in 8x13 one"),
+        Arg::with_name("scale")
+            .long("scale")
+            .value_name("N")
+            .takes_value(true)
+            .help("Draws every pixel of the font in png, jpeg and gif files as an N by N square. Defaults to 1"),
+    ]
+}
+
+// only used by the markdown format
+fn max_width_arg() -> Arg<'static, 'static> {
+    Arg::with_name("max-width")
+        .long("max-width")
+        .value_name("N")
+        .takes_value(true)
+        .help("Cuts the lines of markdown files off after N characters, so chats don't wrap them")
+}
+
+// where the files go
+fn output_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("output")
+            .short("o")
+            .long("output")
+            .value_name("PATH")
+            .takes_value(true)
+            .help("Where to put the output. Existing folders and names without an extension are used as a folder, anything else as the output file. Use - to write to stdout. Defaults to the \"output\" folder"),
+        Arg::with_name("name")
+            .long("name")
+            .value_name("TEMPLATE")
+            .takes_value(true)
+            .help("Output file name template, e.g. {stem}_{mode}_{width}.{ext}\n{stem}: image name without extension, {name}: image name, {mode}: the mode (cblock for colored blocks), {width}: width in characters, {ext}: the format, {row} and {column}: where a tile is. Defaults to {stem}.{ext}"),
+        Arg::with_name("overwrite")
+            .long("overwrite")
+            .value_name("POLICY")
+            .takes_value(true)
+            .possible_values(&["overwrite", "skip", "fail", "suffix"])
+            .help("What to do when an output file already exists. suffix writes to name_1.ext, name_2.ext, ... instead. Defaults to overwrite"),
+    ]
+}
+
+// which files of a folder are used
+fn folder_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("recursive")
+            .short("R")
+            .long("recursive")
+            .help("Also uses the images in subfolders, keeping the same subfolders in the output"),
+        Arg::with_name("ext")
+            .long("ext")
+            .value_name("EXTENSIONS")
+            .takes_value(true)
+            .multiple(true)
+            .use_delimiter(true)
+            .help("Only uses files with these extensions, e.g. --ext png,jpg"),
+        Arg::with_name("glob")
+            .long("glob")
+            .value_name("PATTERN")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .help("Only uses files whose name matches the pattern, e.g. --glob 'frame*'"),
+    ]
+}
+
+fn jobs_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("jobs")
+        .short("j")
+        .long("jobs")
+        .value_name("N")
+        .takes_value(true)
+        .help(help)
+}
+
+fn watch_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("watch").long("watch").help(help)
+}
+
+fn delay_arg() -> Arg<'static, 'static> {
+    Arg::with_name("delay")
+        .short("d")
+        .long("delay")
+        .value_name("MS")
+        .takes_value(true)
+        .help("Milliseconds between frames when playing a folder. GIFs use their own delays. Defaults to 200")
+}
+
+// ===================================================== subcommands =====================================================
+
+fn convert(options: &Options) {
+    let filename = options.filename();
+    let (renderer, render) = (renderer(options), render_options(options));
+    let encoder = encoder(options);
+    if input_is_folder(&filename) {
+        fail(&format!("{} is a folder, use `batch` to convert folders", filename));
+    }
+    if is_grid(&filename) {
+        if options.flag("watch") {
+            fail("--watch only works with images, not with cell grids");
+        }
+        convert_grid(options, &filename, encoder);
+        return;
+    }
+    check(render.check(encoder.as_deref()));
+    if encoder.is_none() && options.is_set("output") {
+        fail("--output needs a --format");
+    }
+    let tiles = options.value("tiles").map(|tiles| {
+        tiles
+            .split_once('x')
+            .and_then(|(columns, rows)| Some((columns.parse::<u32>().ok()?, rows.parse::<u32>().ok()?)))
+            .filter(|&(columns, rows)| columns > 0 && rows > 0)
+            .unwrap_or_else(|| fail("--tiles must be COLUMNSxROWS, e.g. 3x2"))
+    });
+    let naming = naming(options);
+    if options.flag("watch") {
+        if filename == "-" {
+            fail("stdin can't be watched, --watch needs a file");
+        }
+        check(watch(&filename, &FileFilter::default(), |_| {
+            let converted = match &encoder {
+                Some(encoder) => convert_image(&filename, renderer.as_ref(), encoder.as_ref(), &render, &naming, tiles),
+                None => {
+                    redraw(std::slice::from_ref(&filename), renderer.as_ref(), &render);
+                    Ok(())
+                }
+            };
+            if let Err(e) = converted {
+                eprintln!("Failed {}: {}", filename, e);
+            }
+        }));
+        return;
+    }
+    match encoder {
+        Some(encoder) => convert_image(&filename, renderer.as_ref(), encoder.as_ref(), &render, &naming, tiles).unwrap_or_else(|e| fail(&e)),
+        None => {
+            write_term_with(&filename, renderer.as_ref(), &render);
+            return;
+        }
+    }
+    status!("Completed!")
+}
+
+// convert one image into its file, or into a file per tile
+fn convert_image(
+    filename: &str,
+    renderer: &dyn Renderer,
+    encoder: &dyn Encoder,
+    render: &RenderOptions,
+    naming: &OutputNaming,
+    tiles: Option<(u32, u32)>,
+) -> Result<(), String> {
+    let img = load_image(filename).map_err(|e| format!("can't read {}: {}", filename, e))?;
+    status!("Processing {}: {}x{}", filename, img.width(), img.height());
+    let (width, height) = render.rotated_size(img.width(), img.height());
+    if render.crop.is_some_and(|crop| crop.rect(width, height).is_none()) {
+        return Err(format!("the crop is outside of the {}x{} image", width, height));
+    }
+    if let Some((columns, rows)) = tiles {
+        // turn and crop first, then every tile is drawn on its own
+        let img = crop_image(&transform_image(&img, render), render.crop).unwrap().into_owned();
+        let mut tile_render = render.clone().rotate(0).flip_horizontal(false).flip_vertical(false);
+        tile_render.crop = None;
+        for (i, tile) in split_tiles(&img, columns, rows).iter().enumerate() {
+            let (row, column) = (i / columns as usize + 1, i % columns as usize + 1);
+            let (contents, width) = encode_image(tile, renderer, encoder, &tile_render)?;
+            let destination = naming.tile_destination(filename, &renderer.mode_name(render), width, encoder.extension(), row, column);
+            match naming.write(&destination, &contents) {
+                Ok(Some(path)) if path == Path::new("-") => {}
+                Ok(Some(path)) => status!("Written to {}", path.display()),
+                Ok(None) => status!("Skipping tile {},{}: {} already exists", row, column, destination.display()),
+                Err(e) => fail(&format!("can't write {}: {}", destination.display(), e)),
+            }
+        }
+    } else {
+        let (contents, width) = encode_image(&img, renderer, encoder, render)?;
+        write_output(naming, filename, &renderer.mode_name(render), width, encoder.extension(), &contents);
+    }
+    Ok(())
+}
+
+// a json or bin cell grid into another format, without drawing it again
+fn convert_grid(options: &Options, filename: &str, encoder: Option<Arc<dyn Encoder>>) {
+    let grid = Grid::load(filename).unwrap_or_else(|e| fail(&e));
+    let canvas = match grid.frames.as_slice() {
+        [frame] => &frame.canvas,
+        [] => fail(&format!("{} has no frames", filename)),
+        frames => fail(&format!("{} has {} frames, use `animate` or `play` for animations", filename, frames.len())),
+    };
+    let mut render = render_options(options);
+    render.mode = grid.mode.parse().unwrap_or(render.mode);
+    render.renderer = Some(grid.mode.clone());
+    // the colors are already in the cells, formats that can't show them just leave them out
+    if canvas.is_colored() && !options.is_set("color-depth") {
+        render.color = ColorDepth::TrueColor;
+    }
+    let encoder = match encoder {
+        Some(encoder) => encoder,
+        None if options.is_set("output") => fail("--output needs a --format"),
+        None => {
+            canvas.print();
+            return;
+        }
+    };
+    let contents = encoder.encode(canvas, &render).unwrap_or_else(|e| fail(&e));
+    write_output(&naming(options), filename, &grid.mode, art_width(&canvas.to_text()), encoder.extension(), &contents);
+    status!("Completed!")
+}
+
+fn batch(options: &Options) {
+    let filename = options.filename();
+    let (renderer, render) = (renderer(options), render_options(options));
+    let encoder = encoder(options).unwrap_or_else(|| fail("batch needs a --format"));
+    if !input_is_folder(&filename) {
+        fail(&format!("{} isn't a folder, use `convert` for single images", filename));
+    }
+    check(render.check(Some(encoder.as_ref())));
+    let naming = naming(options);
+    if naming.file.is_some() {
+        fail("--output has to be a folder when converting a folder");
+    }
+    let workers = workers(options);
+    if options.flag("watch") {
+        // outputs written into the watched folder, like png files, mustn't be converted again and again
+        let mut written = HashSet::new();
+        check(watch(&filename, &filter(options), |changed| {
+            let changed: Vec<String> = changed
+                .iter()
+                .filter(|file| fs::canonicalize(file).map_or(true, |file| !written.contains(&file)))
+                .cloned()
+                .collect();
+            if changed.is_empty() {
+                return;
+            }
+            let summary = files2fold(changed, &filename, &naming, renderer.clone(), encoder.clone(), &render, workers);
+            written.extend(summary.written.iter().filter_map(|path| fs::canonicalize(path).ok()));
+            if !is_quiet() {
+                eprint!("{}", summary);
+            }
+        }));
+        return;
+    }
+    let summary = imgfold2fold(&filename, &naming, renderer, encoder, &render, &filter(options), workers);
+    if !is_quiet() {
+        eprint!("{}", summary);
+    }
+    if !summary.failed.is_empty() {
         std::process::exit(1);
     }
+    status!("Completed!")
+}
+
+fn play(options: &Options) {
+    let filename = options.filename();
+    let (renderer, render) = (renderer(options), render_options(options));
+    let msdelay = delay(options);
+    check(render.check(None));
+    if options.flag("watch") {
+        if filename == "-" {
+            fail("stdin can't be watched, --watch needs a file or folder");
+        }
+        if is_grid(&filename) {
+            fail("--watch only works with images, not with cell grids");
+        }
+        check(watch(&filename, &filter(options), |changed| redraw(changed, renderer.as_ref(), &render)));
+        return;
+    }
+    if input_is_folder(&filename) || is_gif(&filename) || is_grid(&filename) {
+        imgfold2term_with(&filename, renderer, &render, msdelay, &filter(options));
+    } else {
+        write_term_with(&filename, renderer.as_ref(), &render);
+    }
+}
+
+// draw the images that changed over what's in the terminal, with their names above them when there's more than one.
+// gifs only show their first frame
+fn redraw(files: &[String], renderer: &dyn Renderer, render: &RenderOptions) {
+    // draw over the last ones instead of below them
+    print!("\x1b[2J\x1b[H");
+    for file in files {
+        match load_image(file) {
+            Ok(img) => {
+                if files.len() > 1 {
+                    println!("{}:", file);
+                }
+                renderer.render(&img, &render.for_term()).print();
+            }
+            Err(e) => eprintln!("Failed {}: {}", file, e),
+        }
+    }
+}
+
+fn animate(options: &Options) {
+    let filename = options.filename();
+    let (renderer, render) = (renderer(options), render_options(options));
+    let (msdelay, format) = (delay(options), options.value("animation-format").unwrap());
+    // recordings of single images are fine, they're what `play` shows for them
+    if !input_is_folder(&filename) && !is_gif(&filename) && !is_grid(&filename) && format != "cast" {
+        fail(&format!("{} isn't a folder, a GIF or a cell grid", filename));
+    }
+    let naming = naming(options);
+    let (contents, width, ext) = match format.as_str() {
+        "html" => {
+            check(render.check(Some(&OutputFormat::Html)));
+            let player = imgfold2player(&filename, renderer.clone(), &render, msdelay, &filter(options));
+            player.map(|(contents, width)| (contents.into_bytes(), width, "html"))
+        }
+        "gif" => {
+            let raster = raster_encoder(options, RasterFormat::Png);
+            check(render.check(Some(&raster)));
+            let gif = imgfold2gif(&filename, renderer.clone(), &raster, &render, msdelay, &filter(options));
+            gif.map(|(contents, width)| (contents, width, "gif"))
+        }
+        "cast" => {
+            check(render.check(None));
+            let cast = imgfold2cast(&filename, renderer.clone(), &render, msdelay, &filter(options));
+            cast.map(|(contents, width)| (contents.into_bytes(), width, "cast"))
+        }
+        "json" | "bin" => {
+            check(render.check(None));
+            let binary = format == "bin";
+            let grid = imgfold2grid(&filename, renderer.clone(), &render, msdelay, &filter(options), binary);
+            grid.map(|(contents, width)| (contents, width, if binary { "bin" } else { "json" }))
+        }
+        format => fail(&format!("unknown animation format '{}', expected html, gif, cast, json or bin", format)),
+    }
+    .unwrap_or_else(|e| fail(&e));
+    write_output(&naming, &filename, &renderer.mode_name(&render), width, ext, &contents);
+    status!("Completed!")
+}
+
+fn info(options: &Options) {
+    let filename = options.filename();
+    let render = render_options(options);
+    let msdelay = delay(options);
+    check(render.check(None));
+    if is_grid(&filename) {
+        // grids are already art, there's nothing to draw again
+        let grid = Grid::load(&filename).unwrap_or_else(|e| fail(&e));
+        let (width, height) = grid.dimensions();
+        let colors = if grid.frames.iter().any(|frame| frame.canvas.is_colored()) { "colored" } else { "plain" };
+        println!("{}: {} cell grid, {}x{} characters, {}, {}", filename, grid.mode, width, height, colors, frame_count(grid.frames.len()));
+        println!("delays: {}", delays(grid.frames.iter().map(|frame| frame.delay.unwrap_or(msdelay))));
+        return;
+    }
+    if input_is_folder(&filename) {
+        let files = find_files(&filename, &filter(options));
+        // only the images that can be read become frames, the same as when the folder is played.
+        // the headers are enough to tell, so nothing gets decoded but the first image
+        let mut frames = Vec::new();
+        for file in &files {
+            match upright_dimensions(file) {
+                Ok(dimensions) => frames.push((file, dimensions)),
+                Err(e) => eprintln!("Skipping {}: {}", file, e),
+            }
+        }
+        // animations are sized after the first image that can be read
+        let &(first, (width, height)) = frames
+            .first()
+            .unwrap_or_else(|| fail(&format!("No readable images found in {}", filename)));
+        let color = load_image(first).unwrap_or_else(|e| fail(&format!("can't read {}: {}", first, e))).color();
+        let skipped = files.len() - frames.len();
+        if skipped > 0 {
+            println!("{}: {} images and {} skipped that can't be read, sized after the first one", filename, frames.len(), skipped);
+        } else {
+            println!("{}: {} images, sized after the first one", filename, frames.len());
+        }
+        println!("{}: {}x{}, {:?}", first, width, height, color);
+        println!("delays: {}", delays(std::iter::repeat_n(msdelay, frames.len())));
+        print_grid_sizes(width, height, &render);
+        return;
+    }
+    let img = load_image(&filename).unwrap_or_else(|e| fail(&format!("can't read {}: {}", filename, e)));
+    if is_gif(&filename) {
+        let frames = gif_delays(&filename).unwrap_or_else(|e| fail(&e));
+        println!("{}: {}x{}, {:?}, {}", filename, img.width(), img.height(), img.color(), frame_count(frames.len()));
+        println!("delays: {}", delays(frames.into_iter()));
+    } else {
+        println!("{}: {}x{}, {:?}", filename, img.width(), img.height(), img.color());
+    }
+    print_grid_sizes(img.width(), img.height(), &render);
+}
+
+// how big the art of every mode comes out in files and in the terminal
+fn print_grid_sizes(image_width: u32, image_height: u32, render: &RenderOptions) {
+    let size = match render.size {
+        Size::Auto => String::from("at full size"),
+        Size::Width(columns) => format!("at --width {}", columns),
+        Size::Compress(compress) => format!("at --compress {}", compress),
+    };
+    println!("art size in characters, columns x lines, {}:", size);
+    println!("    {:<10} {:>12} {:>12}", "mode", "file", "terminal");
+    for mode in Mode::ALL {
+        let render = render.clone().mode(mode);
+        let (width, height) = render.grid_size(image_width, image_height);
+        let (term_width, term_height) = render.for_term().grid_size(image_width, image_height);
+        let (file, term) = (format!("{}x{}", width, height), format!("{}x{}", term_width, term_height));
+        println!("    {:<10} {:>12} {:>12}", mode.name(), file, term);
+    }
+}
+
+fn frame_count(frames: usize) -> String {
+    if frames == 1 {
+        String::from("1 frame")
+    } else {
+        format!("{} frames", frames)
+    }
+}
+
+// the delays of the frames in milliseconds, once if they're all the same
+fn delays(delays: impl Iterator<Item = u64>) -> String {
+    let delays: Vec<u64> = delays.collect();
+    match delays.first() {
+        None => String::from("none"),
+        Some(first) if delays.iter().all(|delay| delay == first) => format!("{}ms each", first),
+        _ => delays.iter().map(|delay| format!("{}ms", delay)).collect::<Vec<_>>().join(", "),
+    }
+}
+
+// print where the settings come from and what they end up being
+fn config_show(options: &Options) {
+    if options.sources.is_empty() {
+        println!("# no config files found, looked for:");
+        for path in Config::paths() {
+            println!("#     {}", path.display());
+        }
+    } else {
+        for path in &options.sources {
+            println!("# read {}", path.display());
+        }
+    }
+    if let Some(preset) = &options.preset {
+        println!("# using preset {}", preset);
+    }
+    let mut settings = builtin_settings();
+    settings.extend(options.config.clone());
+    match toml::to_string(&settings) {
+        Ok(text) => print!("{}", text),
+        Err(e) => fail(&format!("can't print the settings: {}", e)),
+    }
+}
+
+// ===================================================== helpers =====================================================
 
-    // if input has a "." in it, it's not a folder.
-    let filename = matches.value_of_lossy("filename").unwrap();
-    let split_filename = filename.split(".").collect::<Vec<&str>>();
-    let mut folder = true;
-    if split_filename.len() > 1 {
-        folder = false
-    }
-
-    // (filename, folder, block, braille, html, text, whitespace)
-    let options = (
-        matches.value_of_lossy("filename").unwrap(),
-        folder,
-        matches.is_present("block"),
-        matches.is_present("braille"),
-        matches.is_present("html"),
-        matches.is_present("text"),
-        matches.is_present("whitespace"),
-        matches.is_present("colored"),
-    );
-    let destination = get_destination(&filename, "output");
-    match options{
-        // single img, block, html, uncolored
-        (filename, false, true, false, true, false, _, false) => {
-            write_html(&destination, img_to_asciistring(&filename, 1, 2, false));
-        }
-        // single img, block, html, colored
-        (filename, false, true, false, true, false, _, true) => {
-            write_chtml(&filename, &destination);
-        }
-        // single img, block, txt
-        (filename, false, true, false,  false, true, _, _) => {
-            write_txt(&destination, img_to_asciistring(&filename, 1, 2, false));
-        }
-        // single img, block, terminal
-        (filename, false, true, false, false, false, _, colored) => {
-            write_term(filename.as_ref(), "block", false, colored);
-        }
-        // single img, braille, html
-        (filename, false, false, true, true, false, whitespace, _) => {
-            write_html(&destination, img_to_braillestring(filename.as_ref(), 2, whitespace, false))
-        }
-        // single img, braille, txt
-        (filename, false, false, true, false, true, whitespace, _) => {
-            write_txt(&destination, img_to_braillestring(filename.as_ref(), 1, whitespace, false))
-        }
-        // single img, braille, terminal
-        (filename, false, false, true, false, false, whitespace, _) => {
-            write_term(filename.as_ref(), "braille", whitespace, false);
-        }
-        // folder, block, html, uncolored
-        (filename, true, true, false, true, false, _, false) => {
-            imgfold2asciifold(&filename, "output", false, "block", "html")
-        }
-        // folder, block, html, colored
-        (filename, true, true, false, true, false, _, true) => {
-            imgfold2chtml(&filename, "output");
-        }
-        // folder, block, txt
-        (filename, true, true, false, false, true, _, _) => {
-            imgfold2asciifold(&filename, "output", false, "block", "txt")
-        }
-        // folder, block, terminal
-        (filename, true, true, false, false, false, _, colored) => {
-            imgfold2term(&filename, "block", false, colored, 200);
-        }
-        // folder, braille, html
-        (filename, true, false, true, true, false, whitespace, _) => {
-            imgfold2asciifold(&filename, "output", whitespace, "braille", "html")
-        }
-        // folder, braille, txt
-        (filename, true, false, true, false, true, whitespace, _) => {
-            imgfold2asciifold(&filename, "output", whitespace, "braille", "txt")
-        }
-        // folder, braille, terminal
-        (filename, true, false, true, false, false, whitespace, _) => {
-            imgfold2term(&filename, "braille", whitespace, false, 200);
-        }
-
-        // For catching cases I may have missed
-        _ => println!("{:?} case not covered, oops", options)
-    }
-    println!("Completed!")
-}
\ No newline at end of file
+// the mode, out of the registry
+fn renderer(options: &Options) -> Arc<dyn Renderer> {
+    options.registry.renderer(&options.value("mode").unwrap()).unwrap_or_else(|e| fail(&e))
+}
+
+// the format out of the registry, or None for the terminal
+fn encoder(options: &Options) -> Option<Arc<dyn Encoder>> {
+    let format = options.value("format")?;
+    let encoder = options.registry.encoder(&format).unwrap_or_else(|e| fail(&e));
+    // the built-in formats, set up with their own options
+    match format.as_str() {
+        "ansi" => {
+            let fields = ["sauce-title", "sauce-author", "sauce-group"].map(|key| options.value(key));
+            let sauce = options.flag("sauce") || fields.iter().any(Option::is_some);
+            if !sauce && !options.flag("cp437") {
+                return Some(encoder);
+            }
+            let sauce = sauce.then(|| {
+                let [title, author, group] = fields.map(Option::unwrap_or_default);
+                Sauce { title, author, group }
+            });
+            Some(Arc::new(AnsiEncoder { cp437: options.flag("cp437"), sauce }))
+        }
+        "svg" if ["font", "font-size", "line-height"].iter().any(|key| options.is_set(key)) => {
+            let mut svg = SvgEncoder::default();
+            if let Some(font) = options.value("font") {
+                svg.font = font;
+            }
+            let number = |key: &str, default: f32| match options.value(key) {
+                Some(value) => match value.parse::<f32>() {
+                    Ok(number) if number > 0. => number,
+                    _ => fail(&format!("--{} must be a positive number", key)),
+                },
+                None => default,
+            };
+            svg.font_size = number("font-size", svg.font_size);
+            svg.line_height = number("line-height", svg.line_height);
+            Some(Arc::new(svg))
+        }
+        "markdown" if options.is_set("max-width") => {
+            let max_width = options.value("max-width").unwrap().parse().unwrap_or_else(|_| fail("--max-width must be a number"));
+            Some(Arc::new(MarkdownEncoder { max_width: Some(max_width) }))
+        }
+        "png" | "jpeg" if ["bitmap-font", "scale"].iter().any(|key| options.is_set(key)) => {
+            let format = if format == "png" { RasterFormat::Png } else { RasterFormat::Jpeg(90) };
+            Some(Arc::new(raster_encoder(options, format)))
+        }
+        _ => Some(encoder),
+    }
+}
+
+// the png and jpeg formats and gif animations, with the font and scale from the options
+fn raster_encoder(options: &Options, format: RasterFormat) -> RasterEncoder {
+    let mut raster = RasterEncoder::new(format);
+    if let Some(path) = options.value("bitmap-font") {
+        raster.font = BitmapFont::load(Path::new(&path)).unwrap_or_else(|e| fail(&e));
+    }
+    if let Some(scale) = options.value("scale") {
+        raster.scale = match scale.parse() {
+            Ok(scale) if scale > 0 => scale,
+            _ => fail("--scale must be a positive number"),
+        };
+    }
+    raster
+}
+
+// how the art looks, out of the options
+fn render_options(options: &Options) -> RenderOptions {
+    let number = |key: &str| -> u32 {
+        let value = options.value(key).unwrap();
+        value.parse().unwrap_or_else(|_| fail(&format!("--{} must be a number", key)))
+    };
+    // modes from outside the crate get the settings of the block mode
+    let mode = options.value("mode").unwrap().parse().unwrap_or(Mode::Block);
+    let color = match options.value("color-depth") {
+        Some(depth) => depth.parse().unwrap_or_else(|e: String| fail(&e)),
+        None if options.flag("color") => ColorDepth::TrueColor,
+        None => ColorDepth::Mono,
+    };
+    let threshold = options.value("threshold").unwrap();
+    let mut render = RenderOptions::new(mode)
+        .color(color)
+        .threshold(threshold.parse().unwrap_or_else(|_| fail("--threshold must be a number from 0 to 255")))
+        .dither(options.flag("dither"))
+        .invert(options.flag("invert"))
+        .whitespace(options.flag("whitespace"))
+        .pad(options.flag("pad"))
+        .flip_horizontal(options.flag("flip-horizontal"))
+        .flip_vertical(options.flag("flip-vertical"));
+    if let Some(degrees) = options.value("rotate") {
+        render = render.rotate(degrees.parse().unwrap_or_else(|_| fail("--rotate must be 0, 90, 180 or 270")));
+    }
+    if let Some(crop) = options.value("crop") {
+        render = render.crop(crop.parse().unwrap_or_else(|e: String| fail(&e)));
+    }
+    // width and compress both set the size, so the one on the command line wins over the one in the config
+    let size_key = ["width", "compress"]
+        .into_iter()
+        .find(|key| options.matches.occurrences_of(key) > 0)
+        .or_else(|| ["width", "compress"].into_iter().find(|key| options.is_set(key)));
+    render = match size_key {
+        Some("width") => render.width(number("width")),
+        Some(_) => render.compress(number("compress")),
+        None => render,
+    };
+    if let Some(ramp) = options.value("ramp") {
+        render = render.ramp(&ramp);
+    }
+    render
+}
+
+fn delay(options: &Options) -> u64 {
+    match options.value("delay").unwrap().parse() {
+        Ok(msdelay) => msdelay,
+        Err(_) => fail("--delay must be a number of milliseconds"),
+    }
+}
+
+fn naming(options: &Options) -> OutputNaming {
+    let mut naming = OutputNaming::default().with_output(&options.value("output").unwrap());
+    naming.template = options.value("name").unwrap();
+    naming.overwrite = options.value("overwrite").unwrap().parse().unwrap_or_else(|e: String| fail(&e));
+    naming
+}
+
+fn filter(options: &Options) -> FileFilter {
+    FileFilter {
+        recursive: options.flag("recursive"),
+        extensions: options.values("ext"),
+        globs: options.values("glob"),
+    }
+}
+
+// the number of --jobs, one per core if it's not given
+fn workers(options: &Options) -> usize {
+    match options.value("jobs").unwrap().parse::<usize>() {
+        Ok(n) if n > 0 => n,
+        _ => fail("--jobs must be a positive number"),
+    }
+}
+
+fn input_is_folder(filename: &str) -> bool {
+    if filename == "-" {
+        return false;
+    }
+    match is_folder(filename) {
+        Ok(folder) => folder,
+        Err(e) => fail(&format!("can't read {}: {}", filename, e)),
+    }
+}
+
+fn check(result: Result<(), String>) {
+    if let Err(e) = result {
+        fail(&e);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
+
+// write a single image's output, following the naming template and overwrite policy
+fn write_output(naming: &OutputNaming, filename: &str, mode: &str, width: usize, ext: &str, contents: &[u8]) {
+    let destination = naming.destination(filename, None, mode, width, ext);
+    match naming.write(&destination, contents) {
+        Ok(Some(path)) if path == Path::new("-") => {}
+        Ok(Some(path)) => status!("Written to {}", path.display()),
+        Ok(None) => status!("Skipping {}: {} already exists", filename, destination.display()),
+        Err(e) => fail(&format!("can't write {}: {}", destination.display(), e)),
+    }
+}
@@ -0,0 +1,496 @@
+// everything that decides how an image turns into art, so functions don't need a long list of arguments
+use crate::Encoder;
+use std::{fmt, str::FromStr};
+
+// what the art is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mode {
+    // shaded block elements, one pixel per cell
+    Block,
+    // braille characters, a 2x4 grid of pixels per cell
+    Braille,
+    // ascii characters from dense to light, one pixel per cell
+    Ascii,
+    // half blocks, two pixels on top of each other per cell
+    HalfBlock,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 4] = [Mode::Block, Mode::Braille, Mode::Ascii, Mode::HalfBlock];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Block => "block",
+            Mode::Braille => "braille",
+            Mode::Ascii => "ascii",
+            Mode::HalfBlock => "halfblock",
+        }
+    }
+
+    // how many pixels wide and tall a cell is
+    pub fn cell_size(self) -> (u32, u32) {
+        match self {
+            Mode::Braille => (2, 4),
+            Mode::HalfBlock => (1, 2),
+            Mode::Block | Mode::Ascii => (1, 1),
+        }
+    }
+}
+
+// what kind of file the art is written into
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    Txt,
+    Html,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 2] = [OutputFormat::Txt, OutputFormat::Html];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Txt => "txt",
+            OutputFormat::Html => "html",
+        }
+    }
+
+    // the file extension, without the dot
+    pub fn extension(self) -> &'static str {
+        self.name()
+    }
+
+    // whether the format can show colors at all
+    pub fn supports_color(self) -> bool {
+        self != OutputFormat::Txt
+    }
+}
+
+// how many colors the output can use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum ColorDepth {
+    // no colors
+    #[default]
+    Mono,
+    // the 16 basic terminal colors
+    Ansi16,
+    // the 256 color terminal palette
+    Ansi256,
+    // any rgb color
+    TrueColor,
+}
+
+impl ColorDepth {
+    pub const ALL: [ColorDepth; 4] = [ColorDepth::Mono, ColorDepth::Ansi16, ColorDepth::Ansi256, ColorDepth::TrueColor];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorDepth::Mono => "mono",
+            ColorDepth::Ansi16 => "16",
+            ColorDepth::Ansi256 => "256",
+            ColorDepth::TrueColor => "truecolor",
+        }
+    }
+
+    pub fn is_colored(self) -> bool {
+        self != ColorDepth::Mono
+    }
+}
+
+// parse any of the enums above from their names
+macro_rules! from_name {
+    ($type:ident, $what:literal) => {
+        impl FromStr for $type {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $type::ALL.into_iter().find(|value| value.name() == s).ok_or_else(|| {
+                    let names: Vec<&str> = $type::ALL.iter().map(|value| value.name()).collect();
+                    format!("unknown {} '{}', expected one of {}", $what, s, names.join(", "))
+                })
+            }
+        }
+
+        impl fmt::Display for $type {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    };
+}
+
+from_name!(Mode, "mode");
+from_name!(OutputFormat, "format");
+from_name!(ColorDepth, "color depth");
+
+// how big the art ends up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Size {
+    // full size for files, shrunk to fit for the terminal
+    #[default]
+    Auto,
+    // only use every n-th pixel
+    Compress(u32),
+    // at most this many characters wide
+    Width(u32),
+}
+
+// how wide the terminal output is allowed to get when the size is Auto
+pub const TERM_WIDTH: u32 = 200;
+
+// a distance in pixels, or in percent of the image's width or height
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    Pixels(u32),
+    Percent(f32),
+}
+
+impl Length {
+    // in pixels, for an image this long
+    pub fn of(self, full: u32) -> u32 {
+        match self {
+            Length::Pixels(pixels) => pixels,
+            Length::Percent(percent) => (full as f32 * percent / 100.).round() as u32,
+        }
+    }
+}
+
+impl FromStr for Length {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.parse::<f32>() {
+                Ok(percent) if (0. ..=100.).contains(&percent) => Ok(Length::Percent(percent)),
+                _ => Err(format!("'{}' isn't a percentage from 0% to 100%", s)),
+            },
+            None => s.parse().map(Length::Pixels).map_err(|_| format!("'{}' isn't a number of pixels or a percentage", s)),
+        }
+    }
+}
+
+// the part of the image that's turned into art. written like WIDTHxHEIGHT+X+Y, where every number
+// can be pixels or a percentage, e.g. 640x480+100+50 or 50%x50%+25%+25%. without +X+Y it starts at the top left
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    pub x: Length,
+    pub y: Length,
+    pub width: Length,
+    pub height: Length,
+}
+
+impl Crop {
+    // the rectangle (x, y, width, height) in an image of this size, cut off at the edges. None if nothing is left
+    pub fn rect(&self, image_width: u32, image_height: u32) -> Option<(u32, u32, u32, u32)> {
+        let (x, y) = (self.x.of(image_width), self.y.of(image_height));
+        let width = self.width.of(image_width).min(image_width.saturating_sub(x));
+        let height = self.height.of(image_height).min(image_height.saturating_sub(y));
+        (width > 0 && height > 0).then_some((x, y, width, height))
+    }
+}
+
+impl FromStr for Crop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid crop '{}', expected WIDTHxHEIGHT+X+Y like 640x480+100+50 or 50%x50%+25%+25%", s);
+        let mut parts = s.split('+');
+        let (width, height) = parts.next().and_then(|size| size.split_once('x')).ok_or_else(invalid)?;
+        let (x, y) = match (parts.next(), parts.next(), parts.next()) {
+            (None, _, _) => ("0", "0"),
+            (Some(x), Some(y), None) => (x, y),
+            _ => return Err(invalid()),
+        };
+        Ok(Crop { x: x.parse()?, y: y.parse()?, width: width.parse()?, height: height.parse()? })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    pub mode: Mode,
+    // the name of the renderer that draws the art, for formats that keep it. None is the mode,
+    // anything else is a renderer from a registry that only borrows the mode's settings
+    pub renderer: Option<String>,
+    pub size: Size,
+    pub color: ColorDepth,
+    // characters from the darkest pixel to the brightest, for block and ascii. None uses the mode's own
+    pub ramp: Option<Vec<String>>,
+    // pixels darker than this are dots in braille and ink in halfblock
+    pub threshold: u8,
+    // spread the rounding error to the neighbouring pixels (Floyd-Steinberg), so gradients survive
+    pub dither: bool,
+    // use spaces instead of ⡀ for empty braille cells
+    pub whitespace: bool,
+    // fill every line of text formats up to the same width instead of trimming the ends, see Canvas::to_padded_text
+    pub pad: bool,
+    // swap dark and bright
+    pub invert: bool,
+    // how many characters wide a block or ascii cell is. None uses 2, which keeps pixels roughly square
+    pub charamount: Option<usize>,
+    // turn the image clockwise by this many degrees, 0, 90, 180 or 270
+    pub rotate: u32,
+    // mirror the image left to right and top to bottom, after turning it
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    // only turn this part of the image into art, after turning and mirroring it
+    pub crop: Option<Crop>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions::new(Mode::Block)
+    }
+}
+
+impl RenderOptions {
+    pub fn new(mode: Mode) -> Self {
+        RenderOptions {
+            mode,
+            renderer: None,
+            size: Size::Auto,
+            color: ColorDepth::Mono,
+            ramp: None,
+            threshold: 128,
+            dither: false,
+            whitespace: false,
+            pad: false,
+            invert: false,
+            charamount: None,
+            rotate: 0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            crop: None,
+        }
+    }
+
+    // the name of whatever drew the art, the mode's own name if it's a mode
+    pub fn renderer_name(&self) -> &str {
+        self.renderer.as_deref().unwrap_or(self.mode.name())
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn size(mut self, size: Size) -> Self {
+        self.size = size;
+        self
+    }
+
+    // shorthand for size(Size::Width(width))
+    pub fn width(self, width: u32) -> Self {
+        self.size(Size::Width(width))
+    }
+
+    // shorthand for size(Size::Compress(compress))
+    pub fn compress(self, compress: u32) -> Self {
+        self.size(Size::Compress(compress))
+    }
+
+    pub fn color(mut self, color: ColorDepth) -> Self {
+        self.color = color;
+        self
+    }
+
+    // every character of the string is one step of the ramp
+    pub fn ramp(mut self, ramp: &str) -> Self {
+        self.ramp = Some(ramp.chars().map(String::from).collect());
+        self
+    }
+
+    pub fn threshold(mut self, threshold: u8) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn whitespace(mut self, whitespace: bool) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    pub fn pad(mut self, pad: bool) -> Self {
+        self.pad = pad;
+        self
+    }
+
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    pub fn charamount(mut self, charamount: usize) -> Self {
+        self.charamount = Some(charamount);
+        self
+    }
+
+    pub fn rotate(mut self, degrees: u32) -> Self {
+        self.rotate = degrees;
+        self
+    }
+
+    pub fn flip_horizontal(mut self, flip: bool) -> Self {
+        self.flip_horizontal = flip;
+        self
+    }
+
+    pub fn flip_vertical(mut self, flip: bool) -> Self {
+        self.flip_vertical = flip;
+        self
+    }
+
+    // how big an image of this size is after turning it
+    pub fn rotated_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.rotate % 180 == 90 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    pub fn crop(mut self, crop: Crop) -> Self {
+        self.crop = Some(crop);
+        self
+    }
+
+    // how many characters wide a cell is
+    pub fn cell_width(&self) -> usize {
+        match self.mode {
+            Mode::Block | Mode::Ascii => self.charamount.unwrap_or(2),
+            Mode::Braille | Mode::HalfBlock => 1,
+        }
+    }
+
+    // what lines are padded with. braille gets the empty braille pattern, which is as wide as the other braille
+    // characters in every font and isn't whitespace that editors strip, unless spaces were asked for
+    pub fn blank(&self) -> char {
+        if self.mode == Mode::Braille && !self.whitespace {
+            '\u{2800}'
+        } else {
+            ' '
+        }
+    }
+
+    // how many pixels apart the sampled pixels are, for an image this wide. with a crop, only the cropped part counts
+    pub fn compress_for(&self, image_width: u32) -> u32 {
+        let image_width = match self.crop {
+            Some(crop) => crop.width.of(image_width).min(image_width.saturating_sub(crop.x.of(image_width))),
+            None => image_width,
+        };
+        let max_columns = match self.size {
+            Size::Auto => return 1,
+            Size::Compress(compress) => return compress.max(1),
+            Size::Width(columns) => columns.max(1),
+        };
+        let (cell_width, _) = self.mode.cell_size();
+        let columns = |compress: u32| image_width.div_ceil(compress * cell_width) as usize * self.cell_width();
+        let mut compress = 1;
+        while columns(compress) > max_columns as usize && compress < image_width.max(1) {
+            compress += 1;
+        }
+        compress
+    }
+
+    // how many characters wide and how many lines tall the art of an image this big comes out, without drawing it
+    pub fn grid_size(&self, image_width: u32, image_height: u32) -> (usize, usize) {
+        let (width, height) = self.rotated_size(image_width, image_height);
+        let compress = self.compress_for(width);
+        let (width, height) = match self.crop {
+            Some(crop) => crop.rect(width, height).map_or((0, 0), |(_, _, width, height)| (width, height)),
+            None => (width, height),
+        };
+        let (width, height) = (width.div_ceil(compress) as usize, height.div_ceil(compress) as usize);
+        match self.mode {
+            // only whole 2x4 grids are drawn
+            Mode::Braille => (width / 2, height / 4),
+            Mode::HalfBlock => (width, height.div_ceil(2)),
+            Mode::Block | Mode::Ascii => (width * self.cell_width(), height),
+        }
+    }
+
+    // the same options, set up for printing into the terminal: auto size fits the terminal,
+    // blocks are 3 characters wide and bright pixels are ink since terminals are usually dark
+    pub fn for_term(&self) -> RenderOptions {
+        let mut options = self.clone();
+        if options.size == Size::Auto {
+            options.size = Size::Width(TERM_WIDTH);
+        }
+        if options.mode == Mode::Block && options.charamount.is_none() {
+            options.charamount = Some(3);
+        }
+        options.invert = !options.invert;
+        options
+    }
+
+    // check that the options make sense with an output format. no format means the terminal
+    pub fn check(&self, format: Option<&dyn Encoder>) -> Result<(), String> {
+        if let Some(format) = format {
+            if self.color.is_colored() && !format.supports_color() {
+                return Err(format!("{} files can't hold colors, use --format html instead", format.name()));
+            }
+        }
+        if self.ramp.as_ref().is_some_and(Vec::is_empty) {
+            return Err(String::from("the ramp needs at least one character"));
+        }
+        if ![0, 90, 180, 270].contains(&self.rotate) {
+            return Err(String::from("images can only be rotated by 90, 180 or 270 degrees"));
+        }
+        if self.size == Size::Compress(0) || self.size == Size::Width(0) {
+            return Err(String::from("the size has to be bigger than 0"));
+        }
+        Ok(())
+    }
+
+    // the name of the mode in file names, colored blocks have always been called cblock
+    pub fn mode_name(&self) -> String {
+        if self.mode == Mode::Block && self.color.is_colored() {
+            String::from("cblock")
+        } else {
+            self.mode.name().to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lengths() {
+        assert_eq!("640".parse(), Ok(Length::Pixels(640)));
+        assert_eq!("12.5%".parse(), Ok(Length::Percent(12.5)));
+        assert_eq!("100%".parse(), Ok(Length::Percent(100.)));
+        assert!("101%".parse::<Length>().is_err());
+        assert!("-5".parse::<Length>().is_err());
+        assert!("px".parse::<Length>().is_err());
+        assert_eq!(Length::Percent(25.).of(200), 50);
+        assert_eq!(Length::Pixels(30).of(200), 30);
+    }
+
+    #[test]
+    fn parses_crops() {
+        let crop: Crop = "640x480+100+50".parse().unwrap();
+        assert_eq!(
+            crop,
+            Crop { x: Length::Pixels(100), y: Length::Pixels(50), width: Length::Pixels(640), height: Length::Pixels(480) }
+        );
+        let crop: Crop = "50%x50%".parse().unwrap();
+        assert_eq!((crop.x, crop.y), (Length::Pixels(0), Length::Pixels(0)));
+        assert_eq!(crop.rect(400, 300), Some((0, 0, 200, 150)));
+        for invalid in ["640", "640x480+100", "640x480+1+2+3", "axb"] {
+            assert!(invalid.parse::<Crop>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn crops_are_cut_off_at_the_edges() {
+        let crop: Crop = "50%x50%+75%+25%".parse().unwrap();
+        assert_eq!(crop.rect(400, 300), Some((300, 75, 100, 150)));
+        let crop: Crop = "10x10+400+0".parse().unwrap();
+        assert_eq!(crop.rect(400, 300), None);
+    }
+}
@@ -0,0 +1,104 @@
+// the fixed palettes of 16 and 256 color terminals, for when truecolor isn't an option
+use crate::{options::ColorDepth, Rgb};
+
+// the standard xterm colors, in the order of their ansi numbers
+pub const ANSI16: [Rgb; 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+// the levels of each channel in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+// the closest color out of a palette
+fn nearest(rgb: Rgb, palette: impl Iterator<Item = Rgb>) -> u8 {
+    palette
+        .enumerate()
+        .min_by_key(|&(_, color)| distance(rgb, color))
+        .map_or(0, |(index, _)| index as u8)
+}
+
+// the ansi number (0-15) of the closest of the 16 colors
+pub fn nearest_ansi16(rgb: Rgb) -> u8 {
+    nearest(rgb, ANSI16.iter().copied())
+}
+
+// the rgb value of a color of the 256 color palette
+pub fn ansi256_rgb(index: u8) -> Rgb {
+    match index {
+        0..=15 => ANSI16[index as usize],
+        16..=231 => {
+            let index = index - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[(index / 6 % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+// the number (16-255) of the closest color of the 256 color palette.
+// the first 16 are left out since terminals often change them
+pub fn nearest_ansi256(rgb: Rgb) -> u8 {
+    16 + nearest(rgb, (16..=255).map(ansi256_rgb))
+}
+
+// the colors of mirc and most other irc clients, 0-15 are the classic ones
+pub const MIRC: [Rgb; 99] = [
+    (255, 255, 255), (0, 0, 0), (0, 0, 127), (0, 147, 0), (255, 0, 0), (127, 0, 0), (156, 0, 156), (252, 127, 0),
+    (255, 255, 0), (0, 252, 0), (0, 147, 147), (0, 255, 255), (0, 0, 252), (255, 0, 255), (127, 127, 127), (210, 210, 210),
+    // 16-98, the same 12 hues at 6 brightnesses and then 11 greys
+    (71, 0, 0), (71, 33, 0), (71, 71, 0), (50, 71, 0), (0, 71, 0), (0, 71, 44),
+    (0, 71, 71), (0, 39, 71), (0, 0, 71), (46, 0, 71), (71, 0, 71), (71, 0, 42),
+    (116, 0, 0), (116, 58, 0), (116, 116, 0), (81, 116, 0), (0, 116, 0), (0, 116, 73),
+    (0, 116, 116), (0, 64, 116), (0, 0, 116), (75, 0, 116), (116, 0, 116), (116, 0, 69),
+    (181, 0, 0), (181, 99, 0), (181, 181, 0), (125, 181, 0), (0, 181, 0), (0, 181, 113),
+    (0, 181, 181), (0, 99, 181), (0, 0, 181), (117, 0, 181), (181, 0, 181), (181, 0, 107),
+    (255, 0, 0), (255, 140, 0), (255, 255, 0), (178, 255, 0), (0, 255, 0), (0, 255, 160),
+    (0, 255, 255), (0, 140, 255), (0, 0, 255), (165, 0, 255), (255, 0, 255), (255, 0, 152),
+    (255, 89, 89), (255, 180, 89), (255, 255, 113), (207, 255, 96), (111, 255, 111), (101, 255, 201),
+    (109, 255, 255), (89, 180, 255), (89, 89, 255), (196, 89, 255), (255, 102, 255), (255, 89, 188),
+    (255, 156, 156), (255, 211, 156), (255, 255, 156), (226, 255, 156), (156, 255, 156), (156, 255, 219),
+    (156, 255, 255), (156, 211, 255), (156, 156, 255), (220, 156, 255), (255, 156, 255), (255, 148, 211),
+    (0, 0, 0), (19, 19, 19), (40, 40, 40), (54, 54, 54), (77, 77, 77), (101, 101, 101),
+    (129, 129, 129), (159, 159, 159), (188, 188, 188), (226, 226, 226), (255, 255, 255),
+];
+
+// the mirc number of the closest color, out of the 16 classic ones or all 99
+pub fn nearest_mirc(rgb: Rgb, extended: bool) -> u8 {
+    let count = if extended { MIRC.len() } else { 16 };
+    nearest(rgb, MIRC[..count].iter().copied())
+}
+
+// the color that ends up on screen at a color depth
+pub fn quantize(rgb: Rgb, depth: ColorDepth) -> Rgb {
+    match depth {
+        ColorDepth::Ansi16 => ANSI16[nearest_ansi16(rgb) as usize],
+        ColorDepth::Ansi256 => ansi256_rgb(nearest_ansi256(rgb)),
+        _ => rgb,
+    }
+}
@@ -0,0 +1,564 @@
+// png and jpeg files of the art, drawn with a bitmap font, for places where text doesn't show up right,
+// and animated gifs of a whole sequence of frames.
+// the built-in font covers ascii, and block elements and braille are drawn to fill the whole cell so they line up.
+// other fonts can be loaded from BDF and PSF (version 1 and 2) files.
+use crate::{ansi::CP437_HIGH, Canvas, Encoder, Frame, RenderOptions, Rgb};
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    Delay, ImageOutputFormat, RgbImage, RgbaImage,
+};
+use std::{collections::HashMap, fs, io::Cursor, path::Path};
+
+// the color of cells without colors, the same as html and svg
+const DEFAULT_FG: Rgb = (0, 0, 0);
+const DEFAULT_BG: Rgb = (255, 255, 255);
+// the biggest glyphs a font file can have, anything bigger is a broken file
+const MAX_GLYPH_SIZE: usize = 256;
+
+// the ascii glyphs of the X11 misc-fixed 8x13 font, which is public domain. one byte per row, the left pixel is the top bit
+const FIXED_8X13: [[u8; 13]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x10, 0x00, 0x00], // '!'
+    [0x00, 0x00, 0x24, 0x24, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '"'
+    [0x00, 0x00, 0x00, 0x24, 0x24, 0x7e, 0x24, 0x7e, 0x24, 0x24, 0x00, 0x00, 0x00], // '#'
+    [0x00, 0x00, 0x10, 0x3c, 0x50, 0x50, 0x38, 0x14, 0x14, 0x78, 0x10, 0x00, 0x00], // '$'
+    [0x00, 0x00, 0x22, 0x52, 0x24, 0x08, 0x08, 0x10, 0x24, 0x2a, 0x44, 0x00, 0x00], // '%'
+    [0x00, 0x00, 0x00, 0x00, 0x30, 0x48, 0x48, 0x30, 0x4a, 0x44, 0x3a, 0x00, 0x00], // '&'
+    [0x00, 0x00, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x00, 0x00, 0x04, 0x08, 0x08, 0x10, 0x10, 0x10, 0x08, 0x08, 0x04, 0x00, 0x00], // '('
+    [0x00, 0x00, 0x20, 0x10, 0x10, 0x08, 0x08, 0x08, 0x10, 0x10, 0x20, 0x00, 0x00], // ')'
+    [0x00, 0x00, 0x24, 0x18, 0x7e, 0x18, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '*'
+    [0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0x7c, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00], // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x30, 0x40, 0x00], // ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x38, 0x10, 0x00], // '.'
+    [0x00, 0x00, 0x02, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x80, 0x00, 0x00], // '/'
+    [0x00, 0x00, 0x18, 0x24, 0x42, 0x42, 0x42, 0x42, 0x42, 0x24, 0x18, 0x00, 0x00], // '0'
+    [0x00, 0x00, 0x10, 0x30, 0x50, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7c, 0x00, 0x00], // '1'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x02, 0x04, 0x18, 0x20, 0x40, 0x7e, 0x00, 0x00], // '2'
+    [0x00, 0x00, 0x7e, 0x02, 0x04, 0x08, 0x1c, 0x02, 0x02, 0x42, 0x3c, 0x00, 0x00], // '3'
+    [0x00, 0x00, 0x04, 0x0c, 0x14, 0x24, 0x44, 0x44, 0x7e, 0x04, 0x04, 0x00, 0x00], // '4'
+    [0x00, 0x00, 0x7e, 0x40, 0x40, 0x5c, 0x62, 0x02, 0x02, 0x42, 0x3c, 0x00, 0x00], // '5'
+    [0x00, 0x00, 0x1c, 0x20, 0x40, 0x40, 0x5c, 0x62, 0x42, 0x42, 0x3c, 0x00, 0x00], // '6'
+    [0x00, 0x00, 0x7e, 0x02, 0x04, 0x08, 0x08, 0x10, 0x10, 0x20, 0x20, 0x00, 0x00], // '7'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x42, 0x3c, 0x42, 0x42, 0x42, 0x3c, 0x00, 0x00], // '8'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x46, 0x3a, 0x02, 0x02, 0x04, 0x38, 0x00, 0x00], // '9'
+    [0x00, 0x00, 0x00, 0x00, 0x10, 0x38, 0x10, 0x00, 0x00, 0x10, 0x38, 0x10, 0x00], // ':'
+    [0x00, 0x00, 0x00, 0x00, 0x10, 0x38, 0x10, 0x00, 0x00, 0x38, 0x30, 0x40, 0x00], // ';'
+    [0x00, 0x00, 0x02, 0x04, 0x08, 0x10, 0x20, 0x10, 0x08, 0x04, 0x02, 0x00, 0x00], // '<'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x7e, 0x00, 0x00, 0x00, 0x00], // '='
+    [0x00, 0x00, 0x40, 0x20, 0x10, 0x08, 0x04, 0x08, 0x10, 0x20, 0x40, 0x00, 0x00], // '>'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x02, 0x04, 0x08, 0x08, 0x00, 0x08, 0x00, 0x00], // '?'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x4e, 0x52, 0x56, 0x4a, 0x40, 0x3c, 0x00, 0x00], // '@'
+    [0x00, 0x00, 0x18, 0x24, 0x42, 0x42, 0x42, 0x7e, 0x42, 0x42, 0x42, 0x00, 0x00], // 'A'
+    [0x00, 0x00, 0x78, 0x44, 0x42, 0x44, 0x78, 0x44, 0x42, 0x44, 0x78, 0x00, 0x00], // 'B'
+    [0x00, 0x00, 0x3c, 0x42, 0x40, 0x40, 0x40, 0x40, 0x40, 0x42, 0x3c, 0x00, 0x00], // 'C'
+    [0x00, 0x00, 0x78, 0x44, 0x42, 0x42, 0x42, 0x42, 0x42, 0x44, 0x78, 0x00, 0x00], // 'D'
+    [0x00, 0x00, 0x7e, 0x40, 0x40, 0x40, 0x78, 0x40, 0x40, 0x40, 0x7e, 0x00, 0x00], // 'E'
+    [0x00, 0x00, 0x7e, 0x40, 0x40, 0x40, 0x78, 0x40, 0x40, 0x40, 0x40, 0x00, 0x00], // 'F'
+    [0x00, 0x00, 0x3c, 0x42, 0x40, 0x40, 0x40, 0x4e, 0x42, 0x46, 0x3a, 0x00, 0x00], // 'G'
+    [0x00, 0x00, 0x42, 0x42, 0x42, 0x42, 0x7e, 0x42, 0x42, 0x42, 0x42, 0x00, 0x00], // 'H'
+    [0x00, 0x00, 0x7c, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7c, 0x00, 0x00], // 'I'
+    [0x00, 0x00, 0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x44, 0x38, 0x00, 0x00], // 'J'
+    [0x00, 0x00, 0x42, 0x44, 0x48, 0x50, 0x60, 0x50, 0x48, 0x44, 0x42, 0x00, 0x00], // 'K'
+    [0x00, 0x00, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x40, 0x7e, 0x00, 0x00], // 'L'
+    [0x00, 0x00, 0x82, 0x82, 0xc6, 0xaa, 0x92, 0x92, 0x82, 0x82, 0x82, 0x00, 0x00], // 'M'
+    [0x00, 0x00, 0x42, 0x42, 0x62, 0x52, 0x4a, 0x46, 0x42, 0x42, 0x42, 0x00, 0x00], // 'N'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x3c, 0x00, 0x00], // 'O'
+    [0x00, 0x00, 0x7c, 0x42, 0x42, 0x42, 0x7c, 0x40, 0x40, 0x40, 0x40, 0x00, 0x00], // 'P'
+    [0x00, 0x00, 0x3c, 0x42, 0x42, 0x42, 0x42, 0x42, 0x52, 0x4a, 0x3c, 0x02, 0x00], // 'Q'
+    [0x00, 0x00, 0x7c, 0x42, 0x42, 0x42, 0x7c, 0x50, 0x48, 0x44, 0x42, 0x00, 0x00], // 'R'
+    [0x00, 0x00, 0x3c, 0x42, 0x40, 0x40, 0x3c, 0x02, 0x02, 0x42, 0x3c, 0x00, 0x00], // 'S'
+    [0x00, 0x00, 0xfe, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00], // 'T'
+    [0x00, 0x00, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x42, 0x3c, 0x00, 0x00], // 'U'
+    [0x00, 0x00, 0x82, 0x82, 0x44, 0x44, 0x44, 0x28, 0x28, 0x28, 0x10, 0x00, 0x00], // 'V'
+    [0x00, 0x00, 0x82, 0x82, 0x82, 0x82, 0x92, 0x92, 0x92, 0xaa, 0x44, 0x00, 0x00], // 'W'
+    [0x00, 0x00, 0x82, 0x82, 0x44, 0x28, 0x10, 0x28, 0x44, 0x82, 0x82, 0x00, 0x00], // 'X'
+    [0x00, 0x00, 0x82, 0x82, 0x44, 0x28, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00], // 'Y'
+    [0x00, 0x00, 0x7e, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x40, 0x7e, 0x00, 0x00], // 'Z'
+    [0x00, 0x00, 0x3c, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x3c, 0x00, 0x00], // '['
+    [0x00, 0x00, 0x80, 0x80, 0x40, 0x20, 0x10, 0x08, 0x04, 0x02, 0x02, 0x00, 0x00], // '\\'
+    [0x00, 0x00, 0x78, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x78, 0x00, 0x00], // ']'
+    [0x00, 0x00, 0x10, 0x28, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '^'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfe, 0x00], // '_'
+    [0x00, 0x10, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '`'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x02, 0x3e, 0x42, 0x46, 0x3a, 0x00, 0x00], // 'a'
+    [0x00, 0x00, 0x40, 0x40, 0x40, 0x5c, 0x62, 0x42, 0x42, 0x62, 0x5c, 0x00, 0x00], // 'b'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x42, 0x40, 0x40, 0x42, 0x3c, 0x00, 0x00], // 'c'
+    [0x00, 0x00, 0x02, 0x02, 0x02, 0x3a, 0x46, 0x42, 0x42, 0x46, 0x3a, 0x00, 0x00], // 'd'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x42, 0x7e, 0x40, 0x42, 0x3c, 0x00, 0x00], // 'e'
+    [0x00, 0x00, 0x1c, 0x22, 0x20, 0x20, 0x7c, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00], // 'f'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3a, 0x44, 0x44, 0x38, 0x40, 0x3c, 0x42, 0x3c], // 'g'
+    [0x00, 0x00, 0x40, 0x40, 0x40, 0x5c, 0x62, 0x42, 0x42, 0x42, 0x42, 0x00, 0x00], // 'h'
+    [0x00, 0x00, 0x00, 0x10, 0x00, 0x30, 0x10, 0x10, 0x10, 0x10, 0x7c, 0x00, 0x00], // 'i'
+    [0x00, 0x00, 0x00, 0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x44, 0x44, 0x38], // 'j'
+    [0x00, 0x00, 0x40, 0x40, 0x40, 0x44, 0x48, 0x70, 0x48, 0x44, 0x42, 0x00, 0x00], // 'k'
+    [0x00, 0x00, 0x30, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7c, 0x00, 0x00], // 'l'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0xec, 0x92, 0x92, 0x92, 0x92, 0x82, 0x00, 0x00], // 'm'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x5c, 0x62, 0x42, 0x42, 0x42, 0x42, 0x00, 0x00], // 'n'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x42, 0x42, 0x42, 0x42, 0x3c, 0x00, 0x00], // 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x5c, 0x62, 0x42, 0x62, 0x5c, 0x40, 0x40, 0x40], // 'p'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3a, 0x46, 0x42, 0x46, 0x3a, 0x02, 0x02, 0x02], // 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x5c, 0x22, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00], // 'r'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x3c, 0x42, 0x30, 0x0c, 0x42, 0x3c, 0x00, 0x00], // 's'
+    [0x00, 0x00, 0x00, 0x20, 0x20, 0x7c, 0x20, 0x20, 0x20, 0x22, 0x1c, 0x00, 0x00], // 't'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x44, 0x44, 0x44, 0x44, 0x44, 0x3a, 0x00, 0x00], // 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x44, 0x44, 0x44, 0x28, 0x28, 0x10, 0x00, 0x00], // 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x82, 0x82, 0x92, 0x92, 0xaa, 0x44, 0x00, 0x00], // 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x24, 0x18, 0x18, 0x24, 0x42, 0x00, 0x00], // 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x42, 0x42, 0x42, 0x46, 0x3a, 0x02, 0x42, 0x3c], // 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7e, 0x04, 0x08, 0x10, 0x20, 0x7e, 0x00, 0x00], // 'z'
+    [0x00, 0x00, 0x0e, 0x10, 0x10, 0x08, 0x30, 0x08, 0x10, 0x10, 0x0e, 0x00, 0x00], // '{'
+    [0x00, 0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00], // '|'
+    [0x00, 0x00, 0x70, 0x08, 0x08, 0x10, 0x0c, 0x10, 0x08, 0x08, 0x70, 0x00, 0x00], // '}'
+    [0x00, 0x00, 0x24, 0x54, 0x48, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // '~'
+];
+
+// a font where every glyph is a grid of on and off pixels of the same size
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitmapFont {
+    pub width: u32,
+    pub height: u32,
+    // the pixels of every glyph, row by row
+    glyphs: HashMap<char, Vec<bool>>,
+}
+
+impl Default for BitmapFont {
+    fn default() -> Self {
+        BitmapFont::builtin()
+    }
+}
+
+impl BitmapFont {
+    // an empty font with glyphs of this size, block elements and braille are always there
+    pub fn new(width: u32, height: u32) -> BitmapFont {
+        let mut font = BitmapFont { width, height, glyphs: HashMap::new() };
+        for c in ['█', '▀', '▄', '▌', '▐', '░', '▒', '▓'].into_iter().chain((0x2800..=0x28ff).filter_map(char::from_u32)) {
+            let glyph = font.drawn_glyph(c);
+            font.glyphs.insert(c, glyph);
+        }
+        font
+    }
+
+    // the 8x13 font that comes with the crate
+    pub fn builtin() -> BitmapFont {
+        let mut font = BitmapFont::new(8, 13);
+        for (c, rows) in (' '..='~').zip(FIXED_8X13) {
+            font.insert(c, &rows, 1);
+        }
+        font
+    }
+
+    // load a BDF or PSF font file
+    pub fn load(path: &Path) -> Result<BitmapFont, String> {
+        let bytes = fs::read(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let font = match bytes {
+            _ if bytes.starts_with(&[0x36, 0x04]) => BitmapFont::from_psf1(&bytes),
+            _ if bytes.starts_with(&[0x72, 0xb5, 0x4a, 0x86]) => BitmapFont::from_psf2(&bytes),
+            _ if bytes.starts_with(b"STARTFONT") => BitmapFont::from_bdf(&String::from_utf8_lossy(&bytes)),
+            _ => Err(String::from("not a BDF or PSF font")),
+        };
+        font.map_err(|e| format!("can't read {}: {}", path.display(), e))
+    }
+
+    // add a glyph from its rows of bits, `row_bytes` long each with the left pixel in the top bit.
+    // block elements and braille are kept the way they're drawn
+    fn insert(&mut self, c: char, rows: &[u8], row_bytes: usize) {
+        if self.glyphs.contains_key(&c) && is_drawn(c) {
+            return;
+        }
+        let mut glyph = Vec::with_capacity((self.width * self.height) as usize);
+        for y in 0..self.height as usize {
+            for x in 0..self.width as usize {
+                let byte = rows.get(y * row_bytes + x / 8).copied().unwrap_or(0);
+                glyph.push(byte & (0x80 >> (x % 8)) != 0);
+            }
+        }
+        self.glyphs.insert(c, glyph);
+    }
+
+    // the pixels of a character, '?' for characters the font doesn't have
+    pub fn glyph(&self, c: char) -> Option<&[bool]> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?')).map(Vec::as_slice)
+    }
+
+    // a block element or braille character, drawn to fill the cell
+    fn drawn_glyph(&self, c: char) -> Vec<bool> {
+        let (w, h) = (self.width, self.height);
+        // braille dots are squares in the middle of a 2x4 grid
+        let dot = ((w / 2).min(h / 4) * 2 / 3).max(1);
+        let braille_dot = |x: u32, y: u32, bits: u32| {
+            let (column, row) = ((x * 2 / w).min(1), (y * 4 / h).min(3));
+            let bit = match (column, row) {
+                (0, 3) => 6,
+                (1, 3) => 7,
+                (column, row) => column * 3 + row,
+            };
+            let (center_x, center_y) = ((2 * column + 1) * w / 4, (2 * row + 1) * h / 8);
+            bits & (1 << bit) != 0 && x + dot / 2 >= center_x && x + dot / 2 < center_x + dot && y + dot / 2 >= center_y && y + dot / 2 < center_y + dot
+        };
+        let mut glyph = Vec::with_capacity((w * h) as usize);
+        for y in 0..h {
+            for x in 0..w {
+                glyph.push(match c {
+                    '█' => true,
+                    '▀' => y < h / 2,
+                    '▄' => y >= h / 2,
+                    '▌' => x < w / 2,
+                    '▐' => x >= w / 2,
+                    '░' => x % 2 == 0 && y % 2 == 0,
+                    '▒' => (x + y) % 2 == 0,
+                    '▓' => !(x % 2 == 1 && y % 2 == 1),
+                    _ => braille_dot(x, y, c as u32 - 0x2800),
+                });
+            }
+        }
+        glyph
+    }
+
+    // PSF version 1: 8 pixels wide, 256 or 512 glyphs, optionally followed by the unicode characters of every glyph
+    fn from_psf1(bytes: &[u8]) -> Result<BitmapFont, String> {
+        let (mode, height) = match bytes.get(2..4) {
+            Some(&[mode, height]) => (mode, height as usize),
+            _ => return Err(String::from("the PSF header is cut off")),
+        };
+        let count = if mode & 0x01 != 0 { 512 } else { 256 };
+        let glyphs_end = 4 + count * height;
+        if height == 0 || bytes.len() < glyphs_end {
+            return Err(String::from("the PSF file is cut off"));
+        }
+        let mut font = BitmapFont::new(8, height as u32);
+        let glyph = |i: usize| &bytes[4 + i * height..4 + (i + 1) * height];
+        if mode & 0x02 != 0 {
+            // a list of little endian characters for every glyph, ending in 0xffff. 0xfffe starts sequences, which are skipped
+            let table: Vec<u16> = bytes[glyphs_end..].chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+            for (i, entry) in table.split(|&c| c == 0xffff).take(count).enumerate() {
+                for c in entry.iter().take_while(|&&c| c != 0xfffe).filter_map(|&c| char::from_u32(c as u32)) {
+                    font.insert(c, glyph(i), 1);
+                }
+            }
+        } else {
+            // without a table, fonts are in code page 437
+            for i in 0..256 {
+                font.insert(cp437_char(i as u8), glyph(i), 1);
+            }
+        }
+        Ok(font)
+    }
+
+    // PSF version 2: any size, with the unicode characters of every glyph as utf-8 if the first flag is set
+    fn from_psf2(bytes: &[u8]) -> Result<BitmapFont, String> {
+        let field = |i: usize| bytes.get(4 * i..4 * i + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize);
+        let (header_size, flags, count, glyph_size, height, width) = match (2..8).map(field).collect::<Option<Vec<_>>>() {
+            Some(header) => (header[0], header[1], header[2], header[3], header[4], header[5]),
+            None => return Err(String::from("the PSF header is cut off")),
+        };
+        if !(1..=MAX_GLYPH_SIZE).contains(&width) || !(1..=MAX_GLYPH_SIZE).contains(&height) {
+            return Err(format!("{}x{} glyphs are too big, fonts can be up to {}x{}", width, height, MAX_GLYPH_SIZE, MAX_GLYPH_SIZE));
+        }
+        let row_bytes = width.div_ceil(8);
+        let glyphs_end = count.checked_mul(glyph_size).and_then(|size| size.checked_add(header_size));
+        if glyph_size < row_bytes * height || glyphs_end.is_none_or(|end| bytes.len() < end) {
+            return Err(String::from("the PSF file is cut off"));
+        }
+        let glyphs_end = glyphs_end.unwrap();
+        let mut font = BitmapFont::new(width as u32, height as u32);
+        let glyph = |i: usize| &bytes[header_size + i * glyph_size..header_size + (i + 1) * glyph_size];
+        if flags & 0x01 != 0 {
+            // the characters of every glyph end with 0xff, and 0xfe starts sequences, which are skipped
+            for (i, entry) in bytes[glyphs_end..].split(|&b| b == 0xff).take(count).enumerate() {
+                let single = entry.split(|&b| b == 0xfe).next().unwrap_or_default();
+                for c in String::from_utf8_lossy(single).chars().filter(|&c| c != char::REPLACEMENT_CHARACTER) {
+                    font.insert(c, glyph(i), row_bytes);
+                }
+            }
+        } else {
+            for i in 0..count.min(256) {
+                font.insert(cp437_char(i as u8), glyph(i), row_bytes);
+            }
+        }
+        Ok(font)
+    }
+
+    // BDF: a text format with a bounding box for the whole font and a bitmap in hex for every glyph
+    fn from_bdf(text: &str) -> Result<BitmapFont, String> {
+        let numbers = |line: &str| -> Vec<i32> { line.split_whitespace().skip(1).filter_map(|n| n.parse().ok()).collect() };
+        let bounding_box = text
+            .lines()
+            .find(|line| line.starts_with("FONTBOUNDINGBOX"))
+            .map(numbers)
+            .filter(|numbers| numbers.len() == 4)
+            .ok_or_else(|| String::from("the BDF file has no FONTBOUNDINGBOX"))?;
+        let max = MAX_GLYPH_SIZE as i32;
+        if !(1..=max).contains(&bounding_box[0]) || !(1..=max).contains(&bounding_box[1]) || bounding_box[2..].iter().any(|n| n.abs() > max) {
+            return Err(format!("the FONTBOUNDINGBOX is too big, fonts can be up to {}x{}", max, max));
+        }
+        let (width, height, x_offset, y_offset) = (bounding_box[0], bounding_box[1], bounding_box[2], bounding_box[3]);
+        let mut font = BitmapFont::new(width as u32, height as u32);
+        let row_bytes = (width as usize).div_ceil(8);
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            if !line.starts_with("STARTCHAR") {
+                continue;
+            }
+            let (mut encoding, mut bbx) = (None, vec![width, height, x_offset, y_offset]);
+            let mut rows = vec![0u8; row_bytes * height as usize];
+            for line in lines.by_ref() {
+                if line.starts_with("ENCODING") {
+                    encoding = numbers(line).first().and_then(|&n| char::from_u32(n as u32));
+                } else if line.starts_with("BBX") {
+                    bbx = numbers(line);
+                    if bbx.len() != 4 || bbx.iter().any(|n| n.abs() > max) {
+                        return Err(format!("the BBX of a glyph has to be 4 numbers up to {}", max));
+                    }
+                } else if line.starts_with("BITMAP") {
+                    // where the glyph's own box goes in the font's box
+                    let (glyph_width, glyph_height) = (bbx[0] as usize, bbx[1]);
+                    let left = bbx[2] - x_offset;
+                    let top = (height + y_offset) - (glyph_height + bbx[3]);
+                    for (y, hex) in lines.by_ref().take_while(|line| !line.starts_with("ENDCHAR")).enumerate() {
+                        // one hex digit is 4 pixels, the leftmost in the top bit
+                        let digits: Vec<u32> = hex.trim().chars().map(|digit| digit.to_digit(16).unwrap_or(0)).collect();
+                        for x in 0..glyph_width.min(digits.len() * 4) {
+                            let (cell_x, cell_y) = (left + x as i32, top + y as i32);
+                            if digits[x / 4] & (8 >> (x % 4)) != 0 && (0..width).contains(&cell_x) && (0..height).contains(&cell_y) {
+                                rows[cell_y as usize * row_bytes + cell_x as usize / 8] |= 0x80 >> (cell_x % 8);
+                            }
+                        }
+                    }
+                    break;
+                }
+            }
+            if let Some(c) = encoding {
+                font.insert(c, &rows, row_bytes);
+            }
+        }
+        Ok(font)
+    }
+}
+
+// block elements and braille, which are drawn instead of taken from the font
+fn is_drawn(c: char) -> bool {
+    matches!(c, '█' | '▀' | '▄' | '▌' | '▐' | '░' | '▒' | '▓' | '\u{2800}'..='\u{28ff}')
+}
+
+fn cp437_char(byte: u8) -> char {
+    if byte < 0x80 {
+        byte as char
+    } else {
+        CP437_HIGH[byte as usize - 0x80]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterEncoder {
+    pub format: RasterFormat,
+    pub font: BitmapFont,
+    // every pixel of the font becomes a square this big
+    pub scale: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RasterFormat {
+    Png,
+    // with the quality from 1 to 100
+    Jpeg(u8),
+}
+
+impl RasterEncoder {
+    pub fn new(format: RasterFormat) -> RasterEncoder {
+        RasterEncoder { format, font: BitmapFont::builtin(), scale: 1 }
+    }
+
+    // draw the canvas, every character of a cell takes up one glyph of the font.
+    // fails if the image would be too big to make
+    pub fn to_image(&self, canvas: &Canvas) -> Result<RgbImage, String> {
+        let scale = self.scale.max(1);
+        let too_big = || format!("{}x{} characters at scale {} are too big to draw", canvas.width(), canvas.height(), scale);
+        // every size is checked, so a big scale or canvas gives an error instead of overflowing
+        let cell_width = self.font.width.checked_mul(scale).ok_or_else(too_big)?;
+        let cell_height = self.font.height.checked_mul(scale).ok_or_else(too_big)?;
+        let width = u32::try_from(canvas.width()).ok().and_then(|w| w.checked_mul(cell_width)).ok_or_else(too_big)?;
+        let height = u32::try_from(canvas.height()).ok().and_then(|h| h.checked_mul(cell_height)).ok_or_else(too_big)?;
+        // the 3 bytes of every pixel have to be countable as well
+        (width as usize).checked_mul(height as usize).and_then(|n| n.checked_mul(3)).ok_or_else(too_big)?;
+        let rgb = |(r, g, b): Rgb| image::Rgb([r, g, b]);
+        let mut img = RgbImage::from_pixel(width.max(1), height.max(1), rgb(DEFAULT_BG));
+        for (row, cells) in canvas.rows.iter().enumerate() {
+            let top = row as u32 * cell_height;
+            let mut column = 0;
+            for cell in cells {
+                let fg = rgb(cell.fg.unwrap_or(DEFAULT_FG));
+                for c in cell.glyph.chars() {
+                    let left = column * cell_width;
+                    column += 1;
+                    if let Some(bg) = cell.bg {
+                        for y in top..top + cell_height {
+                            for x in left..left + cell_width {
+                                img.put_pixel(x, y, rgb(bg));
+                            }
+                        }
+                    }
+                    let glyph = match self.font.glyph(c) {
+                        Some(glyph) if c != ' ' => glyph,
+                        _ => continue,
+                    };
+                    for y in 0..cell_height {
+                        for x in 0..cell_width {
+                            if glyph[((y / scale) * self.font.width + x / scale) as usize] {
+                                img.put_pixel(left + x, top + y, fg);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(img)
+    }
+
+    // an animated gif of the frames. frames without their own delay are up for `msdelay` milliseconds.
+    // every frame is drawn as big as the biggest one, so folders of differently sized images still fit
+    pub fn animation(&self, frames: &[Frame], msdelay: u64, repeat: Repeat) -> Result<Vec<u8>, String> {
+        let images = frames.iter().map(|frame| self.to_image(&frame.canvas)).collect::<Result<Vec<_>, _>>()?;
+        let width = images.iter().map(RgbImage::width).max().unwrap_or(1);
+        let height = images.iter().map(RgbImage::height).max().unwrap_or(1);
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(format!("the animation would be {}x{} pixels, gifs can't be bigger than 65535x65535", width, height));
+        }
+        let mut bytes = Vec::new();
+        {
+            // speed 10 of 30 is a lot faster than the default and the art doesn't have many colors anyway
+            let mut encoder = GifEncoder::new_with_speed(&mut bytes, 10);
+            encoder.set_repeat(repeat).map_err(|e| e.to_string())?;
+            for (img, frame) in images.iter().zip(frames) {
+                let (r, g, b) = DEFAULT_BG;
+                let mut canvas = RgbaImage::from_pixel(width, height, image::Rgba([r, g, b, 255]));
+                for (x, y, &image::Rgb([r, g, b])) in img.enumerate_pixels() {
+                    canvas.put_pixel(x, y, image::Rgba([r, g, b, 255]));
+                }
+                let delay = Delay::from_numer_denom_ms(frame.delay.unwrap_or(msdelay) as u32, 1);
+                encoder.encode_frame(image::Frame::from_parts(canvas, 0, 0, delay)).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl Encoder for RasterEncoder {
+    fn name(&self) -> &str {
+        match self.format {
+            RasterFormat::Png => "png",
+            RasterFormat::Jpeg(_) => "jpeg",
+        }
+    }
+
+    fn extension(&self) -> &str {
+        match self.format {
+            RasterFormat::Png => "png",
+            RasterFormat::Jpeg(_) => "jpg",
+        }
+    }
+
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn media_type(&self) -> &str {
+        match self.format {
+            RasterFormat::Png => "image/png",
+            RasterFormat::Jpeg(_) => "image/jpeg",
+        }
+    }
+
+    fn encode(&self, canvas: &Canvas, _options: &RenderOptions) -> Result<Vec<u8>, String> {
+        let format = match self.format {
+            RasterFormat::Png => ImageOutputFormat::Png,
+            RasterFormat::Jpeg(quality) => ImageOutputFormat::Jpeg(quality),
+        };
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(self.to_image(canvas)?)
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cell;
+
+    const BDF: &str = "STARTFONT 2.1
+FONTBOUNDINGBOX 4 4 0 0
+CHARS 1
+STARTCHAR A
+ENCODING 65
+BBX 2 2 1 1
+BITMAP
+C0
+40
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn reads_bdf_glyphs_into_the_font_box() {
+        let font = BitmapFont::from_bdf(BDF).unwrap();
+        assert_eq!((font.width, font.height), (4, 4));
+        let pixels: String = font.glyph('A').unwrap().iter().map(|&on| if on { '#' } else { '.' }).collect();
+        assert_eq!(pixels, "....\
+                            .##.\
+                            ..#.\
+                            ....");
+    }
+
+    #[test]
+    fn draws_a_glyph_per_character() {
+        let mut canvas = Canvas::new();
+        canvas.push_row(vec![Cell::plain("AA"), Cell::plain("A")]);
+        let encoder = RasterEncoder { format: RasterFormat::Png, font: BitmapFont::from_bdf(BDF).unwrap(), scale: 2 };
+        let img = encoder.to_image(&canvas).unwrap();
+        assert_eq!(img.dimensions(), (3 * 4 * 2, 4 * 2));
+        // too big to count the pixels of
+        let encoder = RasterEncoder { scale: u32::MAX, ..encoder };
+        assert!(encoder.to_image(&canvas).is_err());
+        let encoder = RasterEncoder { scale: u32::MAX / 8, ..encoder };
+        assert!(encoder.encode(&canvas, &RenderOptions::default()).is_err());
+    }
+
+    #[test]
+    fn rejects_broken_bdf_fonts() {
+        assert!(BitmapFont::from_bdf("STARTFONT 2.1\n").is_err());
+        assert!(BitmapFont::from_bdf(&BDF.replace("FONTBOUNDINGBOX 4 4", "FONTBOUNDINGBOX 100000 4")).is_err());
+        assert!(BitmapFont::from_bdf(&BDF.replace("BBX 2 2 1 1", "BBX 2 2 1")).is_err());
+        assert!(BitmapFont::from_bdf(&BDF.replace("BBX 2 2 1 1", "BBX 2 2 -100000 1")).is_err());
+    }
+
+    #[test]
+    fn reads_psf1_fonts() {
+        // 256 glyphs 2 pixels tall, glyph 'A' has the top left pixel set
+        let mut psf = vec![0x36, 0x04, 0, 2];
+        psf.extend(vec![0; 256 * 2]);
+        psf[4 + 'A' as usize * 2] = 0x80;
+        let font = BitmapFont::from_psf1(&psf).unwrap();
+        assert_eq!((font.width, font.height), (8, 2));
+        assert!(font.glyph('A').unwrap()[0]);
+        assert!(!font.glyph('B').unwrap()[0]);
+        assert!(BitmapFont::from_psf1(&psf[..100]).is_err());
+        assert!(BitmapFont::from_psf1(&psf[..3]).is_err());
+    }
+
+    #[test]
+    fn rejects_broken_psf2_fonts() {
+        let header = |count: u32, glyph_size: u32, height: u32, width: u32| -> Vec<u8> {
+            [0x864ab572, 0, 32, 0, count, glyph_size, height, width].iter().flat_map(|n: &u32| n.to_le_bytes()).collect()
+        };
+        assert!(BitmapFont::from_psf2(&header(1, 1, 1, 1)[..20]).is_err());
+        assert!(BitmapFont::from_psf2(&header(1, 1, 1, 1)).is_err());
+        assert!(BitmapFont::from_psf2(&header(1, 1, 100_000, 100_000)).is_err());
+        assert!(BitmapFont::from_psf2(&header(u32::MAX, u32::MAX, 1, 1)).is_err());
+        let mut psf = header(1, 1, 1, 1);
+        psf.push(0x80);
+        assert!(BitmapFont::from_psf2(&psf).is_ok());
+    }
+}
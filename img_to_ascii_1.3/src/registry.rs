@@ -0,0 +1,162 @@
+// modes and formats by name, so new ones can be added from outside the crate.
+// a Renderer draws an image onto a canvas and an Encoder turns a canvas into the bytes of a file.
+// the built-in modes and formats are the Mode and OutputFormat enums, and a Registry starts out with all of them:
+//     let mut registry = Registry::default();
+//     registry.register_renderer(LogoRenderer);
+//     registry.register_encoder(WikiEncoder);
+//     let renderer = registry.renderer("logo")?;
+use crate::{
+    ansi::AnsiEncoder,
+    chat::{BbcodeEncoder, IrcEncoder, MarkdownEncoder},
+    grid::GridEncoder,
+    image_to_canvas,
+    raster::{RasterEncoder, RasterFormat},
+    svg::SvgEncoder,
+    Canvas, Mode, OutputFormat, RenderOptions,
+};
+use image::DynamicImage;
+use std::{collections::BTreeMap, sync::Arc};
+
+pub trait Renderer: Send + Sync {
+    // the name it's picked by, e.g. with --mode
+    fn name(&self) -> &str;
+
+    fn render(&self, img: &DynamicImage, options: &RenderOptions) -> Canvas;
+
+    // what {mode} turns into in output file names
+    fn mode_name(&self, _options: &RenderOptions) -> String {
+        self.name().to_string()
+    }
+}
+
+pub trait Encoder: Send + Sync {
+    // the name it's picked by, e.g. with --format
+    fn name(&self) -> &str;
+
+    // the file extension, without the dot
+    fn extension(&self) -> &str {
+        self.name()
+    }
+
+    // whether the format can show colors at all
+    fn supports_color(&self) -> bool;
+
+    // the Content-Type when the file is sent over http
+    fn media_type(&self) -> &str {
+        "text/plain; charset=utf-8"
+    }
+
+    // the options are the ones the canvas was drawn with
+    fn encode(&self, canvas: &Canvas, options: &RenderOptions) -> Result<Vec<u8>, String>;
+}
+
+impl Renderer for Mode {
+    fn name(&self) -> &str {
+        Mode::name(*self)
+    }
+
+    fn render(&self, img: &DynamicImage, options: &RenderOptions) -> Canvas {
+        if options.mode == *self {
+            image_to_canvas(img, options)
+        } else {
+            image_to_canvas(img, &options.clone().mode(*self))
+        }
+    }
+
+    fn mode_name(&self, options: &RenderOptions) -> String {
+        options.clone().mode(*self).mode_name()
+    }
+}
+
+impl Encoder for OutputFormat {
+    fn name(&self) -> &str {
+        OutputFormat::name(*self)
+    }
+
+    fn supports_color(&self) -> bool {
+        OutputFormat::supports_color(*self)
+    }
+
+    fn media_type(&self) -> &str {
+        match self {
+            OutputFormat::Txt => "text/plain; charset=utf-8",
+            OutputFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    fn encode(&self, canvas: &Canvas, options: &RenderOptions) -> Result<Vec<u8>, String> {
+        match self {
+            OutputFormat::Txt if options.pad => Ok(canvas.to_padded_text(options.blank())?.into_bytes()),
+            OutputFormat::Txt => Ok(canvas.to_text().into_bytes()),
+            OutputFormat::Html => Ok(canvas.to_html().into_bytes()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Registry {
+    renderers: BTreeMap<String, Arc<dyn Renderer>>,
+    encoders: BTreeMap<String, Arc<dyn Encoder>>,
+}
+
+// every built-in mode and format
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::empty();
+        for mode in Mode::ALL {
+            registry.register_renderer(mode);
+        }
+        for format in OutputFormat::ALL {
+            registry.register_encoder(format);
+        }
+        registry.register_encoder(AnsiEncoder::default());
+        registry.register_encoder(SvgEncoder::default());
+        registry.register_encoder(RasterEncoder::new(RasterFormat::Png));
+        registry.register_encoder(RasterEncoder::new(RasterFormat::Jpeg(90)));
+        registry.register_encoder(GridEncoder { binary: false });
+        registry.register_encoder(GridEncoder { binary: true });
+        registry.register_encoder(IrcEncoder);
+        registry.register_encoder(BbcodeEncoder);
+        registry.register_encoder(MarkdownEncoder::default());
+        registry
+    }
+}
+
+impl Registry {
+    // a registry without any modes or formats, not even the built-in ones
+    pub fn empty() -> Self {
+        Registry { renderers: BTreeMap::new(), encoders: BTreeMap::new() }
+    }
+
+    // add a mode, replacing any mode with the same name
+    pub fn register_renderer(&mut self, renderer: impl Renderer + 'static) -> &mut Self {
+        self.renderers.insert(renderer.name().to_string(), Arc::new(renderer));
+        self
+    }
+
+    // add a format, replacing any format with the same name
+    pub fn register_encoder(&mut self, encoder: impl Encoder + 'static) -> &mut Self {
+        self.encoders.insert(encoder.name().to_string(), Arc::new(encoder));
+        self
+    }
+
+    pub fn renderer(&self, name: &str) -> Result<Arc<dyn Renderer>, String> {
+        self.renderers.get(name).cloned().ok_or_else(|| unknown("mode", name, self.renderer_names()))
+    }
+
+    pub fn encoder(&self, name: &str) -> Result<Arc<dyn Encoder>, String> {
+        self.encoders.get(name).cloned().ok_or_else(|| unknown("format", name, self.encoder_names()))
+    }
+
+    pub fn renderer_names(&self) -> Vec<&str> {
+        self.renderers.keys().map(String::as_str).collect()
+    }
+
+    pub fn encoder_names(&self) -> Vec<&str> {
+        self.encoders.keys().map(String::as_str).collect()
+    }
+}
+
+fn unknown(what: &str, name: &str, known: Vec<&str>) -> String {
+    format!("unknown {} '{}', expected one of {}", what, name, known.join(", "))
+}
@@ -0,0 +1,275 @@
+// a tiny http server, so other programs can convert images without running the binary every time
+use crate::{decode_image, encode_image, ColorDepth, Mode, Registry, RenderOptions, Size};
+use std::{
+    io,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    str,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// images bigger than this are turned away
+const MAX_BODY: usize = 64 * 1024 * 1024;
+// the longest request line or header, and how many headers a request can have
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+// how long a client can take to send the next part of its request before it's dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+// the status code, reason and error message of a request that can't be answered
+type Rejection = (u16, &'static str, String);
+// what goes back to the client: the content type and body, or why not
+type Response = Result<(String, Vec<u8>), Rejection>;
+
+const USAGE: &str = "POST an image to /convert to get it back as ascii art.
+options go in the query string:
+    mode=<any mode of --mode> (default block)
+    format=<any format of --format> (default txt)
+    color=mono|16|256|truecolor (just color means truecolor)
+    width=<columns>, compress=<n>, ramp=<characters>, threshold=<0-255>, crop=<WIDTHxHEIGHT+X+Y>
+    rotate=90|180|270, flip-horizontal, flip-vertical
+    whitespace, pad, dither, invert
+e.g. curl --data-binary @cat.png 'http://localhost:8080/convert?mode=braille&format=html&color'
+";
+
+// listen on `addr` and convert every image that gets POSTed to /convert, with the modes and formats of the registry.
+// `workers` threads answer the requests. when all of them are busy and as many connections
+// are already waiting, new connections get a 503 instead of piling up.
+pub fn serve(addr: &str, registry: Registry, workers: usize) -> io::Result<()> {
+    let registry = Arc::new(registry);
+    let listener = TcpListener::bind(addr)?;
+    status!("Listening on http://{}", listener.local_addr()?);
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(workers);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..workers {
+        let (receiver, registry) = (Arc::clone(&receiver), Arc::clone(&registry));
+        thread::spawn(move || loop {
+            // the lock is only held while waiting for a connection, not while answering it
+            let stream = match receiver.lock().unwrap().recv() {
+                Ok(stream) => stream,
+                Err(_) => return,
+            };
+            if let Err(e) = handle(stream, &registry) {
+                eprintln!("Request failed: {}", e);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(mpsc::TrySendError::Full(stream)) = sender.try_send(stream) {
+                    let busy = (503, "Service Unavailable", String::from("too many requests, try again later\n"));
+                    if let Err(e) = respond(stream, "-", Err(busy)) {
+                        eprintln!("Request failed: {}", e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Connection failed: {}", e),
+        }
+    }
+    Ok(())
+}
+
+// read one request and answer it
+fn handle(stream: TcpStream, registry: &Registry) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let request_line = match read_line(&mut reader)? {
+        Some(line) => line,
+        None => return respond(stream, "-", Err((400, "Bad Request", String::from("request line too long\n")))),
+    };
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let target = parts.next().unwrap_or("").to_string();
+    let request = format!("{} {}", method, target);
+    // only the length of the body matters out of the headers
+    let content_length = match content_length(&mut reader)? {
+        Ok(content_length) => content_length,
+        Err(rejection) => return respond(stream, &request, Err(rejection)),
+    };
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let response = match (method.as_str(), path) {
+        ("GET", "/") => Ok((String::from("text/plain; charset=utf-8"), USAGE.as_bytes().to_vec())),
+        ("POST", "/convert") if content_length > MAX_BODY => Err((413, "Payload Too Large", String::from("image too big\n"))),
+        ("POST", "/convert") => {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            convert(&body, query, registry).map_err(|e| (400, "Bad Request", e + "\n"))
+        }
+        _ => Err((404, "Not Found", USAGE.to_string())),
+    };
+    respond(stream, &request, response)
+}
+
+// a line of at most MAX_LINE bytes, None if it's longer
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE as u64 + 1).read_line(&mut line)?;
+    Ok(if line.len() > MAX_LINE { None } else { Some(line) })
+}
+
+// read the headers up to the empty line after them and return the length of the body.
+// too many or too long headers, a length that isn't a number and more than one length are rejected,
+// as the body can't be found for sure then
+fn content_length(reader: &mut impl BufRead) -> io::Result<Result<usize, Rejection>> {
+    let bad_request = |message: &str| Ok(Err((400, "Bad Request", format!("{}\n", message))));
+    let mut content_length = None;
+    for _ in 0..=MAX_HEADERS {
+        let header = match read_line(reader)? {
+            Some(header) => header,
+            None => break,
+        };
+        if header.trim().is_empty() {
+            return Ok(Ok(content_length.unwrap_or(0)));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                let value = value.trim();
+                if content_length.is_some() {
+                    return bad_request("more than one Content-Length");
+                }
+                if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+                    return bad_request("Content-Length has to be a number");
+                }
+                match value.parse() {
+                    Ok(length) => content_length = Some(length),
+                    // too big to even count, so way over MAX_BODY
+                    Err(_) => content_length = Some(usize::MAX),
+                }
+            }
+        }
+    }
+    Ok(Err((431, "Request Header Fields Too Large", String::from("headers too long\n"))))
+}
+
+fn respond(mut stream: TcpStream, request: &str, response: Response) -> io::Result<()> {
+    status!("{} {}", request, response.as_ref().map_or_else(|e| e.0, |_| 200));
+    let (code, reason, content_type, body) = match response {
+        Ok((content_type, body)) => (200, "OK", content_type, body),
+        Err((code, reason, body)) => (code, reason, String::from("text/plain; charset=utf-8"), body.into_bytes()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        code,
+        reason,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+// undo the %XX escapes of a query string, and + for spaces. broken escapes are kept as they are
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// turn the posted image into art, following the options in the query string
+fn convert(body: &[u8], query: &str, registry: &Registry) -> Result<(String, Vec<u8>), String> {
+    let mut options = RenderOptions::default();
+    let (mut renderer, mut encoder) = (registry.renderer("block")?, registry.encoder("txt")?);
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, "true"));
+        let (key, value) = (percent_decode(key), percent_decode(value));
+        let (key, value) = (key.as_str(), value.as_str());
+        let flag = value != "false" && value != "0";
+        let number = |value: &str| value.parse::<u32>().map_err(|_| format!("{} has to be a number", key));
+        match key {
+            "mode" => {
+                renderer = registry.renderer(value)?;
+                options.mode = value.parse().unwrap_or(Mode::Block);
+            }
+            "format" => encoder = registry.encoder(value)?,
+            "color" | "colored" => {
+                options.color = match value {
+                    "true" => ColorDepth::TrueColor,
+                    _ if !flag => ColorDepth::Mono,
+                    _ => value.parse()?,
+                }
+            }
+            "width" => options.size = Size::Width(number(value)?),
+            "compress" => options.size = Size::Compress(number(value)?),
+            "ramp" => options = options.ramp(value),
+            "crop" => options.crop = Some(value.parse()?),
+            "rotate" => options.rotate = number(value)?,
+            "flip-horizontal" => options.flip_horizontal = flag,
+            "flip-vertical" => options.flip_vertical = flag,
+            "threshold" => options.threshold = value.parse().map_err(|_| String::from("threshold has to be 0-255"))?,
+            "whitespace" => options.whitespace = flag,
+            "pad" => options.pad = flag,
+            "dither" => options.dither = flag,
+            "invert" => options.invert = flag,
+            _ => return Err(format!("unknown option '{}'", key)),
+        }
+    }
+    options.check(Some(encoder.as_ref()))?;
+    let img = decode_image(body, None).map_err(|e| e.to_string())?;
+    let (contents, _) = encode_image(&img, renderer.as_ref(), encoder.as_ref(), &options)?;
+    Ok((encoder.media_type().to_string(), contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decodes_queries() {
+        assert_eq!(percent_decode("%23%2e+x"), "#. x");
+        assert_eq!(percent_decode("50%25x50%25"), "50%x50%");
+        assert_eq!(percent_decode("%E2%96%88"), "█");
+        // broken escapes are left alone
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%+1%zz"), "% 1%zz");
+    }
+
+    #[test]
+    fn reads_headers() {
+        let mut headers = "Host: x\r\nContent-Length: 12\r\n\r\nbody".as_bytes();
+        assert_eq!(content_length(&mut headers).unwrap(), Ok(12));
+        assert_eq!(headers, b"body");
+        assert_eq!(content_length(&mut "Host: x\r\n\r\n".as_bytes()).unwrap(), Ok(0));
+        let long = format!("X-Long: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(content_length(&mut long.as_bytes()).unwrap().unwrap_err().0, 431);
+        let many = "X: y\r\n".repeat(MAX_HEADERS + 1) + "\r\n";
+        assert_eq!(content_length(&mut many.as_bytes()).unwrap().unwrap_err().0, 431);
+    }
+
+    #[test]
+    fn rejects_broken_lengths() {
+        for headers in [
+            "Content-Length: twelve\r\n\r\n",
+            "Content-Length: -1\r\n\r\n",
+            "Content-Length: +12\r\n\r\n",
+            "Content-Length:\r\n\r\n",
+            "Content-Length: 12, 12\r\n\r\n",
+            "Content-Length: 12\r\ncontent-length: 12\r\n\r\n",
+            "Content-Length: 12\r\nContent-Length: 13\r\n\r\n",
+        ] {
+            assert_eq!(content_length(&mut headers.as_bytes()).unwrap().unwrap_err().0, 400, "{:?}", headers);
+        }
+        let huge = "Content-Length: 99999999999999999999999999\r\n\r\n";
+        assert_eq!(content_length(&mut huge.as_bytes()).unwrap(), Ok(usize::MAX));
+    }
+}
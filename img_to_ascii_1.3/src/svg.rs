@@ -0,0 +1,109 @@
+// svg files, for putting art into documents and slides where html doesn't fit.
+// every row is a <text> with a <tspan> per run of cells with the same color, and backgrounds are <rect>s behind them.
+use crate::{canvas::runs, html::hex, html_escape, Canvas, Encoder, RenderOptions, Rgb};
+
+// how wide a character of a monospace font is, compared to its size
+const CHAR_WIDTH: f32 = 0.6;
+// how far below the top of the line the baseline is, compared to the font size
+const ASCENT: f32 = 0.8;
+
+// a coordinate without float noise like 7.2000003
+fn num(value: f32) -> String {
+    let rounded = format!("{:.2}", value);
+    rounded.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgEncoder {
+    // the css font-family, it has to be monospace for the columns to line up
+    pub font: String,
+    // the font size in pixels
+    pub font_size: f32,
+    // how tall a row is, compared to the font size
+    pub line_height: f32,
+    // the color behind the whole art, None for transparent
+    pub background: Option<Rgb>,
+}
+
+impl Default for SvgEncoder {
+    fn default() -> Self {
+        SvgEncoder {
+            font: String::from("'DejaVu Sans Mono', Menlo, Consolas, monospace"),
+            font_size: 12.,
+            line_height: 1.,
+            background: Some((255, 255, 255)),
+        }
+    }
+}
+
+impl SvgEncoder {
+    pub fn to_svg(&self, canvas: &Canvas) -> String {
+        let (cell_width, row_height) = (self.font_size * CHAR_WIDTH, self.font_size * self.line_height);
+        let (width, height) = (canvas.width() as f32 * cell_width, canvas.height() as f32 * row_height);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+            w = num(width),
+            h = num(height)
+        );
+        if let Some(background) = self.background {
+            svg += &format!("<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n", hex(background));
+        }
+        svg += &format!(
+            "<g font-family=\"{}\" font-size=\"{}\" fill=\"#000000\" xml:space=\"preserve\">\n",
+            html_escape(&self.font),
+            num(self.font_size)
+        );
+        for (y, row) in canvas.rows.iter().enumerate() {
+            let top = y as f32 * row_height;
+            let (mut rects, mut spans) = (String::new(), String::new());
+            let mut column = 0;
+            for run in runs(row) {
+                let text: String = run.iter().map(|cell| cell.glyph.as_str()).collect();
+                let length = text.chars().count();
+                let x = column as f32 * cell_width;
+                if let Some(bg) = run[0].bg {
+                    rects += &format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                        num(x),
+                        num(top),
+                        num(length as f32 * cell_width),
+                        num(row_height),
+                        hex(bg)
+                    );
+                }
+                // blank runs only matter for their background
+                if !text.trim().is_empty() {
+                    let fill = run[0].fg.map(|fg| format!(" fill=\"{}\"", hex(fg))).unwrap_or_default();
+                    // every run is placed on its own, so fonts that aren't quite monospace can't push the columns apart
+                    spans += &format!("<tspan x=\"{}\"{}>{}</tspan>", num(x), fill, html_escape(&text));
+                }
+                column += length;
+            }
+            svg += &rects;
+            if !spans.is_empty() {
+                let baseline = top + (row_height - self.font_size) / 2. + self.font_size * ASCENT;
+                svg += &format!("<text y=\"{}\">{}</text>\n", num(baseline), spans);
+            }
+        }
+        svg += "</g>\n</svg>\n";
+        svg
+    }
+}
+
+impl Encoder for SvgEncoder {
+    fn name(&self) -> &str {
+        "svg"
+    }
+
+    fn supports_color(&self) -> bool {
+        true
+    }
+
+    fn media_type(&self) -> &str {
+        "image/svg+xml"
+    }
+
+    fn encode(&self, canvas: &Canvas, _options: &RenderOptions) -> Result<Vec<u8>, String> {
+        Ok(self.to_svg(canvas).into_bytes())
+    }
+}
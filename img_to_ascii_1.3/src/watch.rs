@@ -0,0 +1,124 @@
+// convert images again whenever they change, for working on the art while looking at it.
+// on linux inotify says when something in the watched folders changes, everywhere else, or when inotify can't be used,
+// the files are checked every POLL_INTERVAL. either way the files are compared with how they were before,
+// so only the ones that were added or really changed are converted again.
+use crate::{find_files, is_folder, FileFilter};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+// how often the files are checked without inotify
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// how long to wait for more changes after the first one, editors save a file in a few steps
+const SETTLE: Duration = Duration::from_millis(100);
+
+// when a file was last changed and how big it was
+type Stamp = (SystemTime, u64);
+
+// the files that are watched right now, in natural order
+fn snapshot(path: &str, filter: &FileFilter, folder: bool) -> Vec<(String, Stamp)> {
+    let files = if folder { find_files(path, filter) } else { vec![path.to_string()] };
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let metadata = fs::metadata(&file).ok()?;
+            Some((file, (metadata.modified().ok()?, metadata.len())))
+        })
+        .collect()
+}
+
+// the folders changes can show up in. a single file is watched through its folder,
+// since a lot of editors save by writing a new file and renaming it over the old one
+fn folders(path: &str, filter: &FileFilter, folder: bool) -> Vec<PathBuf> {
+    if !folder {
+        let parent = Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty());
+        return vec![parent.unwrap_or(Path::new(".")).to_path_buf()];
+    }
+    let mut folders = vec![PathBuf::from(path)];
+    let mut i = 0;
+    while filter.recursive && i < folders.len() {
+        if let Ok(entries) = fs::read_dir(&folders[i]) {
+            folders.extend(entries.flatten().map(|entry| entry.path()).filter(|path| path.is_dir()));
+        }
+        i += 1;
+    }
+    folders
+}
+
+// what the next change is waited for with
+enum Wakeup {
+    #[cfg(target_os = "linux")]
+    Inotify(inotify::Inotify),
+    Poll,
+}
+
+impl Wakeup {
+    #[cfg(target_os = "linux")]
+    fn new() -> Wakeup {
+        match inotify::Inotify::init() {
+            Ok(inotify) => Wakeup::Inotify(inotify),
+            Err(e) => {
+                status!("Can't use inotify ({}), checking for changes every {}ms instead", e, POLL_INTERVAL.as_millis());
+                Wakeup::Poll
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn new() -> Wakeup {
+        Wakeup::Poll
+    }
+
+    // wait until something might have changed in the folders
+    fn wait(&mut self, folders: &[PathBuf]) {
+        match self {
+            #[cfg(target_os = "linux")]
+            Wakeup::Inotify(inotify) => {
+                use inotify::WatchMask;
+                let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::CREATE | WatchMask::MODIFY;
+                // adding a folder that's already watched does nothing, so new subfolders are picked up on the way
+                for folder in folders {
+                    if let Err(e) = inotify.watches().add(folder, mask) {
+                        status!("Can't watch {} ({}), checking for changes every {}ms instead", folder.display(), e, POLL_INTERVAL.as_millis());
+                        *self = Wakeup::Poll;
+                        return self.wait(folders);
+                    }
+                }
+                let mut buffer = [0; 4096];
+                if inotify.read_events_blocking(&mut buffer).is_err() {
+                    thread::sleep(POLL_INTERVAL);
+                    return;
+                }
+                // let the rest of the save happen, then throw away the events it caused
+                thread::sleep(SETTLE);
+                while inotify.read_events(&mut buffer).is_ok_and(|mut events| events.next().is_some()) {}
+            }
+            Wakeup::Poll => thread::sleep(POLL_INTERVAL),
+        }
+    }
+}
+
+// call `on_change` with every image of `path`, then with the ones that were added or changed since, until the
+// process is stopped. `path` is an image or a folder, the images of a folder are the ones that pass the filter
+pub fn watch(path: &str, filter: &FileFilter, mut on_change: impl FnMut(&[String])) -> Result<(), String> {
+    let folder = is_folder(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    let mut wakeup = Wakeup::new();
+    let mut known: HashMap<String, Stamp> = HashMap::new();
+    loop {
+        let current = snapshot(path, filter, folder);
+        let changed: Vec<String> = current
+            .iter()
+            .filter(|(file, stamp)| known.get(file) != Some(stamp))
+            .map(|(file, _)| file.clone())
+            .collect();
+        known = current.into_iter().collect();
+        if !changed.is_empty() {
+            on_change(&changed);
+        }
+        wakeup.wait(&folders(path, filter, folder));
+    }
+}